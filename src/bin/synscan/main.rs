@@ -0,0 +1,416 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+use synscan::*;
+
+#[cfg(feature = "paddle")]
+mod paddle;
+
+/// Sidereal tracking rate in degrees per second
+const SIDEREAL_RATE: f64 = 360. / 86164.0905;
+/// Rates above this many degrees per second use the motor's fast mode
+const FAST_RATE_THRESHOLD: f64 = 128. * SIDEREAL_RATE;
+/// How long to wait for an axis to come to a stop before changing its motion mode
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+/// Formats an error followed by each of its causes
+fn describe(e: &dyn Error) -> String {
+    let mut description = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        description += &format!(": {}", cause);
+        source = cause.source();
+    }
+    description
+}
+
+/// Controls a SkyWatcher mount through its motor controller
+#[derive(Parser)]
+#[command(name = "synscan", version, about)]
+struct Cli {
+    /// Where the mount is connected: a serial port path such as /dev/ttyUSB0,
+    /// tcp://host:port for a serial bridge or udp://host[:port] for a WiFi mount
+    #[arg(short, long)]
+    endpoint: String,
+
+    /// Baud rate for serial ports
+    #[arg(short, long, default_value_t = 9600)]
+    baud_rate: u32,
+
+    /// How long to wait for each reply, in milliseconds
+    #[arg(short, long, default_value_t = 1000)]
+    timeout: u64,
+
+    /// Print output as JSON
+    #[arg(long)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the motor parameters and board version
+    Info,
+    /// Show the status of both axes
+    Status,
+    /// Show the position of both axes
+    Pos,
+    /// Move an axis to a position in degrees
+    Goto {
+        axis: SingleAxis,
+        degrees: f64,
+        /// Use the slow goto mode
+        #[arg(long)]
+        slow: bool,
+    },
+    /// Turn an axis continuously at a rate in degrees per second. Negative rates turn counterclockwise.
+    Move {
+        axis: SingleAxis,
+        #[arg(allow_negative_numbers = true)]
+        rate: f64,
+    },
+    /// Stop motion
+    Stop {
+        #[arg(default_value = "both")]
+        axis: Axis,
+        /// Stop without decelerating
+        #[arg(long)]
+        instant: bool,
+    },
+    /// Track at the sidereal rate
+    Track {
+        #[arg(default_value = "1")]
+        axis: SingleAxis,
+        /// Track counterclockwise
+        #[arg(long)]
+        reverse: bool,
+    },
+    /// Set the position of an axis in degrees without moving it
+    SetPos {
+        axis: SingleAxis,
+        #[arg(allow_negative_numbers = true)]
+        degrees: f64,
+    },
+    /// Read or write the motor controller EEPROM
+    Eeprom {
+        #[command(subcommand)]
+        command: EepromCommand,
+    },
+    /// Send a raw command and print the reply
+    Raw {
+        /// The command character, e.g. j
+        command: char,
+        axis: Axis,
+        /// The payload, sent as is
+        payload: Option<String>,
+    },
+    /// Drive the mount interactively from the keyboard
+    #[cfg(feature = "paddle")]
+    Paddle,
+}
+
+#[derive(Subcommand)]
+enum EepromCommand {
+    /// Print the contents of the EEPROM as JSON, suitable for restore
+    Dump {
+        axis: SingleAxis,
+        #[arg(long, default_value_t = 0)]
+        start: u32,
+        #[arg(long, default_value_t = 256)]
+        count: u32,
+    },
+    /// Write back the contents saved by dump
+    Restore { file: PathBuf },
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum SingleAxis {
+    #[value(name = "1")]
+    One,
+    #[value(name = "2")]
+    Two,
+}
+
+impl From<SingleAxis> for SingleChannel {
+    fn from(axis: SingleAxis) -> Self {
+        match axis {
+            SingleAxis::One => SingleChannel::Channel1,
+            SingleAxis::Two => SingleChannel::Channel2,
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Axis {
+    #[value(name = "1")]
+    One,
+    #[value(name = "2")]
+    Two,
+    Both,
+}
+
+impl Channel for Axis {
+    fn get_byte(&self) -> u8 {
+        match self {
+            Axis::One => SingleChannel::Channel1.get_byte(),
+            Axis::Two => SingleChannel::Channel2.get_byte(),
+            Axis::Both => MultiChannel::Both.get_byte(),
+        }
+    }
+}
+
+fn channel_name(channel: SingleChannel) -> &'static str {
+    match channel {
+        SingleChannel::Channel1 => "1",
+        SingleChannel::Channel2 => "2",
+    }
+}
+
+fn status_to_json(status: &MotorStatus) -> Value {
+    json!({
+        "mode": match status.mode {
+            DriveMode::Goto => "goto",
+            DriveMode::Tracking => "tracking",
+        },
+        "direction": match status.direction {
+            Direction::Clockwise => "clockwise",
+            Direction::CounterClockwise => "counterclockwise",
+        },
+        "fast": status.fast,
+        "running": status.running,
+        "blocked": status.blocked,
+        "inited": status.inited,
+        "level_switch": status.level_switch,
+    })
+}
+
+/// Builds a JSON object with an entry for each axis
+fn per_axis(mut f: impl FnMut(SingleChannel) -> CliResult<Value>) -> CliResult<Value> {
+    let mut map = Map::new();
+    for channel in SingleChannel::VALUES {
+        map.insert(channel_name(channel).to_string(), f(channel)?);
+    }
+    Ok(Value::Object(map))
+}
+
+/// Stops the axis and waits until it has come to rest, as required before changing motion mode
+fn stop_and_wait<T: SerialPort>(mc: &MotorController<T>, channel: SingleChannel) -> CliResult<()> {
+    mc.stop_motion(channel)?;
+    let start = Instant::now();
+    while mc.inquire_status(channel)?.running {
+        if start.elapsed() > STOP_TIMEOUT {
+            return Err("Timed out waiting for the axis to stop".into());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+/// Whether the rate in degrees per second needs the motor's fast mode
+fn is_fast(rate: f64) -> bool {
+    rate.abs() > FAST_RATE_THRESHOLD
+}
+
+/// Turns the axis continuously at the given rate in degrees per second
+fn start_moving<T: SerialPort>(
+    mc: &MotorController<T>,
+    channel: SingleChannel,
+    rate: f64,
+) -> CliResult<()> {
+    stop_and_wait(mc, channel)?;
+    if rate == 0. {
+        return Ok(());
+    }
+    let direction = if rate < 0. {
+        Direction::CounterClockwise
+    } else {
+        Direction::Clockwise
+    };
+    mc.set_tracking_motion_mode(channel, is_fast(rate), direction)?;
+    mc.set_motion_rate_degrees(channel, rate.abs())?;
+    mc.start_motion(channel)?;
+    Ok(())
+}
+
+/// Runs the command, returning the output if there is any
+fn run<T: SerialPort>(mc: MotorController<T>, command: &Command) -> CliResult<Option<Value>> {
+    let output = match command {
+        Command::Info => {
+            let params = mc.get_motor_parameters();
+            let version = mc.inquire_motor_board_version(SingleChannel::Channel1)?;
+            json!({
+                "counts_per_revolution": per_axis(|c| Ok(json!(params.counts_per_revolution[c])))?,
+                "high_speed_ratio": per_axis(|c| Ok(json!(params.high_speed_ratio[c])))?,
+                "timer_interrupt_freq": params.timer_interrupt_freq,
+                "motor_board_version": {
+                    "major": version.major,
+                    "minor": version.minor,
+                    "mount_code": version.mount_code,
+                },
+            })
+        }
+        Command::Status => per_axis(|c| Ok(status_to_json(&mc.inquire_status(c)?)))?,
+        Command::Pos => per_axis(|c| {
+            let counts = mc.inquire_pos(c)?;
+            Ok(json!({
+                "counts": counts,
+                "degrees": mc.get_motor_parameters().counts_to_degrees(c, counts as f64),
+            }))
+        })?,
+        Command::Goto {
+            axis,
+            degrees,
+            slow,
+        } => {
+            let channel = (*axis).into();
+            stop_and_wait(&mc, channel)?;
+            mc.set_goto_motion_mode(channel, !slow)?;
+            mc.set_goto_target_degrees(channel, *degrees)?;
+            mc.start_motion(channel)?;
+            return Ok(None);
+        }
+        Command::Move { axis, rate } => {
+            start_moving(&mc, (*axis).into(), *rate)?;
+            return Ok(None);
+        }
+        Command::Stop { axis, instant } => {
+            if *instant {
+                mc.instant_stop(*axis)?;
+            } else {
+                mc.stop_motion(*axis)?;
+            }
+            return Ok(None);
+        }
+        Command::Track { axis, reverse } => {
+            let rate = if *reverse {
+                -SIDEREAL_RATE
+            } else {
+                SIDEREAL_RATE
+            };
+            start_moving(&mc, (*axis).into(), rate)?;
+            return Ok(None);
+        }
+        Command::SetPos { axis, degrees } => {
+            mc.set_pos_degrees((*axis).into(), *degrees)?;
+            return Ok(None);
+        }
+        Command::Eeprom {
+            command: EepromCommand::Dump { axis, start, count },
+        } => {
+            let end = start
+                .checked_add(*count)
+                .ok_or("The dump goes past the last EEPROM address")?;
+            let values = mc.dump_eeprom((*axis).into(), *start..end)?;
+            json!({
+                "axis": channel_name((*axis).into()),
+                "values": values
+                    .into_iter()
+                    .map(|(address, value)| json!({ "address": address, "value": value }))
+                    .collect::<Vec<Value>>(),
+            })
+        }
+        Command::Eeprom {
+            command: EepromCommand::Restore { file },
+        } => {
+            let dump: Value = serde_json::from_str(&fs::read_to_string(file)?)?;
+            let channel = match dump["axis"].as_str() {
+                Some("1") => SingleChannel::Channel1,
+                Some("2") => SingleChannel::Channel2,
+                _ => return Err("The dump has no valid axis".into()),
+            };
+            let values = dump["values"].as_array().ok_or("The dump has no values")?;
+            for entry in values {
+                let address = entry["address"].as_u64().ok_or("Invalid address")?;
+                let value = entry["value"].as_u64().ok_or("Invalid value")?;
+                mc.set_eeprom(channel, u32::try_from(address)?, u8::try_from(value)?)?;
+            }
+            return Ok(None);
+        }
+        Command::Raw {
+            command,
+            axis,
+            payload,
+        } => {
+            let command = u8::try_from(*command).map_err(|_| "The command must be ASCII")?;
+            let payload = payload.as_deref().unwrap_or("");
+            let response = mc.send_raw_cmd(command, *axis, payload.as_bytes())?;
+            json!(String::from_utf8_lossy(&response))
+        }
+        #[cfg(feature = "paddle")]
+        Command::Paddle => {
+            paddle::run(mc)?;
+            return Ok(None);
+        }
+    };
+    Ok(Some(output))
+}
+
+/// Prints a value as indented `key: value` lines
+fn print_human(value: &Value, indent: usize) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                if value.is_object() || value.is_array() {
+                    println!("{:indent$}{}:", "", key, indent = indent);
+                    print_human(value, indent + 2);
+                } else {
+                    println!("{:indent$}{}: {}", "", key, value, indent = indent);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                print_human(value, indent);
+            }
+        }
+        Value::String(s) => println!("{:indent$}{}", "", s, indent = indent),
+        value => println!("{:indent$}{}", "", value, indent = indent),
+    }
+}
+
+fn connect_and_run(cli: &Cli) -> CliResult<Option<Value>> {
+    let timeout = Duration::from_millis(cli.timeout);
+    if let Some(addr) = cli.endpoint.strip_prefix("tcp://") {
+        run(MotorController::new_tcp(addr, timeout)?, &cli.command)
+    } else if let Some(addr) = cli.endpoint.strip_prefix("udp://") {
+        let addr = udp::with_default_port(addr);
+        run(MotorController::new_udp(addr, timeout)?, &cli.command)
+    } else {
+        connect_serial_and_run(cli, timeout)
+    }
+}
+
+fn connect_serial_and_run(cli: &Cli, timeout: Duration) -> CliResult<Option<Value>> {
+    let mc = MotorController::new_serialport(cli.endpoint.as_str(), cli.baud_rate, timeout)?;
+    run(mc, &cli.command)
+}
+
+fn main() {
+    let cli = Cli::parse();
+    // EEPROM dumps are always JSON so they can be restored
+    let json = cli.json
+        || matches!(
+            cli.command,
+            Command::Eeprom {
+                command: EepromCommand::Dump { .. }
+            }
+        );
+
+    match connect_and_run(&cli) {
+        Ok(Some(output)) if json => println!("{}", serde_json::to_string_pretty(&output).unwrap()),
+        Ok(Some(output)) => print_human(&output, 0),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("Error: {}", describe(e.as_ref()));
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{describe, is_fast, start_moving, CliResult, SIDEREAL_RATE};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use synscan::*;
+
+/// Slew rates in multiples of the sidereal rate, selected with the keys 1 to 9 like on the hand controller
+const RATES: [f64; 9] = [1., 2., 8., 16., 32., 64., 400., 600., 800.];
+/// How often the display is refreshed from the mount
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Puts the terminal in raw mode on an alternate screen until dropped.
+/// Key releases are reported too if the terminal supports it.
+struct TerminalGuard {
+    enhanced: bool,
+}
+
+impl TerminalGuard {
+    fn new() -> io::Result<TerminalGuard> {
+        terminal::enable_raw_mode()?;
+        let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced {
+            execute!(
+                io::stdout(),
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(TerminalGuard { enhanced })
+    }
+
+    /// Whether key release events are reported. Windows always reports them.
+    fn reports_key_release(&self) -> bool {
+        cfg!(windows) || self.enhanced
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if self.enhanced {
+            let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Paddle<T: SerialPort> {
+    mc: MotorController<T>,
+    rate_index: usize,
+    tracking: bool,
+    /// The direction each axis is being slewed in, as -1, 0 or 1
+    slew: [i8; 2],
+    /// Whether slews stop when their key is released.
+    /// Otherwise they latch until the key is pressed again, the opposite key is pressed or Space.
+    hold_to_slew: bool,
+    /// The rate each axis was last set to in degrees per second, or NaN if it isn't known
+    rates: [f64; 2],
+    state: Option<MountState>,
+    message: String,
+}
+
+impl<T: SerialPort> Paddle<T> {
+    fn rate(&self) -> f64 {
+        RATES[self.rate_index] * SIDEREAL_RATE
+    }
+
+    /// The rate axis 1 turns at when it isn't being slewed
+    fn resting_rate(&self, channel: SingleChannel) -> f64 {
+        match channel {
+            SingleChannel::Channel1 if self.tracking => SIDEREAL_RATE,
+            _ => 0.,
+        }
+    }
+
+    /// The rate the axis should be turning at given the slew and tracking
+    fn target_rate(&self, channel: SingleChannel) -> f64 {
+        match self.slew[index(channel)] {
+            0 => self.resting_rate(channel),
+            direction => direction as f64 * self.rate(),
+        }
+    }
+
+    /// Brings the axis to its target rate. Only stops the axis first if the direction or
+    /// fast mode has to change, so a running slew can speed up or slow down smoothly.
+    fn update(&mut self, channel: SingleChannel) -> CliResult<()> {
+        let i = index(channel);
+        let (current, target) = (self.rates[i], self.target_rate(channel));
+        if current == target {
+            return Ok(());
+        }
+        let same_direction = current * target > 0.;
+        if same_direction && !is_fast(current) && !is_fast(target) {
+            self.mc.set_motion_rate_degrees(channel, target.abs())?;
+        } else {
+            // If this fails part way the axis is in an unknown state
+            self.rates[i] = f64::NAN;
+            start_moving(&self.mc, channel, target)?;
+        }
+        self.rates[i] = target;
+        Ok(())
+    }
+
+    fn slew(&mut self, channel: SingleChannel, direction: i8) -> CliResult<()> {
+        let latching = !self.hold_to_slew;
+        let slew = &mut self.slew[index(channel)];
+        if *slew == direction {
+            // Held keys repeat
+            return Ok(());
+        }
+        // Pressing the opposite direction stops a latched slew
+        *slew = if latching && *slew == -direction {
+            0
+        } else {
+            direction
+        };
+        self.update(channel)
+    }
+
+    /// Stops the slew started by a key when it is released
+    fn release(&mut self, channel: SingleChannel, direction: i8) -> CliResult<()> {
+        if !self.hold_to_slew || self.slew[index(channel)] != direction {
+            return Ok(());
+        }
+        self.slew[index(channel)] = 0;
+        self.update(channel)
+    }
+
+    fn stop(&mut self) -> CliResult<()> {
+        self.slew = [0, 0];
+        for channel in SingleChannel::VALUES {
+            self.update(channel)?;
+        }
+        Ok(())
+    }
+
+    fn toggle_tracking(&mut self) -> CliResult<()> {
+        self.tracking = !self.tracking;
+        self.update(SingleChannel::Channel1)
+    }
+
+    /// Changes the slew rate, including of slews already running
+    fn set_rate_index(&mut self, rate_index: usize) -> CliResult<()> {
+        self.rate_index = rate_index;
+        for channel in SingleChannel::VALUES {
+            if self.slew[index(channel)] != 0 {
+                self.update(channel)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles a key press, returning false when the paddle should exit
+    fn handle_key(&mut self, key: KeyEvent) -> CliResult<bool> {
+        if let Some((channel, direction)) = arrow_slew(key.code) {
+            self.slew(channel, direction)?;
+            return Ok(true);
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(false)
+            }
+            KeyCode::Char(' ') => self.stop()?,
+            KeyCode::Char('t') => self.toggle_tracking()?,
+            KeyCode::Char(c @ '1'..='9') => self.set_rate_index(c as usize - '1' as usize)?,
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        let mut lines = vec![
+            format!(
+                "Rate {} ({}x sidereal)   Tracking {}",
+                self.rate_index + 1,
+                RATES[self.rate_index],
+                if self.tracking { "on" } else { "off" }
+            ),
+            String::new(),
+        ];
+        for channel in SingleChannel::VALUES {
+            let name = match channel {
+                SingleChannel::Channel1 => "Axis 1",
+                SingleChannel::Channel2 => "Axis 2",
+            };
+            let slewing = match self.slew[index(channel)] {
+                1 => "  slewing +",
+                -1 => "  slewing -",
+                _ => "",
+            };
+            lines.push(match &self.state {
+                Some(state) => {
+                    let status = state.status[channel];
+                    format!(
+                        "{}: {:>12.4}°  {:>10}  {:?} {:?}{}{}  {:.5}°/s{}",
+                        name,
+                        state.pos_degrees[channel],
+                        if status.running { "running" } else { "stopped" },
+                        status.mode,
+                        status.direction,
+                        if status.fast { " fast" } else { "" },
+                        if status.blocked { " BLOCKED" } else { "" },
+                        state.motion_rate_degrees[channel],
+                        slewing,
+                    )
+                }
+                None => format!("{}: unknown{}", name, slewing),
+            });
+        }
+        let slew_help = if self.hold_to_slew {
+            "Hold Left/Right: axis 1   Hold Up/Down: axis 2"
+        } else {
+            "Left/Right: axis 1   Up/Down: axis 2   (slews latch: press again to stop)"
+        };
+        lines.extend([
+            String::new(),
+            format!(
+                "{}   1-9: rate   Space: stop   T: tracking   Q: quit",
+                slew_help
+            ),
+            String::new(),
+            self.message.clone(),
+        ]);
+        for line in lines {
+            queue!(out, Print(line), cursor::MoveToNextLine(1))?;
+        }
+        out.flush()
+    }
+}
+
+/// The axis and direction an arrow key slews
+fn arrow_slew(code: KeyCode) -> Option<(SingleChannel, i8)> {
+    match code {
+        KeyCode::Left => Some((SingleChannel::Channel1, -1)),
+        KeyCode::Right => Some((SingleChannel::Channel1, 1)),
+        KeyCode::Down => Some((SingleChannel::Channel2, -1)),
+        KeyCode::Up => Some((SingleChannel::Channel2, 1)),
+        _ => None,
+    }
+}
+
+fn index(channel: SingleChannel) -> usize {
+    match channel {
+        SingleChannel::Channel1 => 0,
+        SingleChannel::Channel2 => 1,
+    }
+}
+
+/// Redraws and handles keys until the user quits
+fn event_loop<T: SerialPort>(paddle: &mut Paddle<T>) -> CliResult<()> {
+    let mut stdout = io::stdout();
+    let mut last_refresh = Instant::now() - REFRESH_INTERVAL;
+    loop {
+        if last_refresh.elapsed() >= REFRESH_INTERVAL {
+            last_refresh = Instant::now();
+            match paddle.mc.inquire_state() {
+                Ok(state) => paddle.state = Some(state),
+                Err(e) => paddle.message = format!("Error: {}", describe(&e)),
+            }
+            paddle.draw(&mut stdout)?;
+        }
+
+        if !event::poll(REFRESH_INTERVAL.saturating_sub(last_refresh.elapsed()))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            let result = match (key.kind, arrow_slew(key.code)) {
+                (KeyEventKind::Press, _) => paddle.handle_key(key),
+                (KeyEventKind::Release, Some((channel, direction))) => {
+                    paddle.release(channel, direction).map(|_| true)
+                }
+                _ => continue,
+            };
+            paddle.message.clear();
+            match result {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(e) => paddle.message = format!("Error: {}", describe(e.as_ref())),
+            }
+            paddle.draw(&mut stdout)?;
+        }
+    }
+}
+
+/// Runs the interactive hand paddle until the user quits.
+/// Both axes are stopped when it exits, including when the terminal fails.
+pub fn run<T: SerialPort>(mc: MotorController<T>) -> CliResult<()> {
+    let mut paddle = Paddle {
+        mc,
+        rate_index: 4,
+        tracking: false,
+        slew: [0, 0],
+        hold_to_slew: false,
+        rates: [f64::NAN; 2],
+        state: None,
+        message: String::new(),
+    };
+
+    let result = TerminalGuard::new().map_err(Into::into).and_then(|guard| {
+        paddle.hold_to_slew = guard.reports_key_release();
+        event_loop(&mut paddle)
+    });
+    let stopped = paddle.mc.stop_motion(MultiChannel::Both);
+    result?;
+    stopped?;
+    Ok(())
+}
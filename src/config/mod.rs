@@ -0,0 +1,210 @@
+//! Describes a mount in a TOML or JSON file and connects to it.
+//!
+//! ```toml
+//! timeout_ms = 1000
+//! autoguide_speed = "half"
+//!
+//! [transport.udp]
+//! address = "192.168.4.1"
+//!
+//! [retry]
+//! max_retries = 2
+//! retry_delay_ms = 50
+//! ```
+
+use crate::udp::UdpSerialPort;
+use crate::util::{SynScanError, SynScanResult};
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use std::{fs, io};
+
+/// How to reach the motor controller
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Transport {
+    /// A serial port, either cabled directly or through a hand controller in PC Direct mode
+    Serial {
+        path: String,
+        #[serde(default = "default_baud_rate")]
+        baud_rate: u32,
+    },
+    /// A serial bridge reachable over TCP
+    Tcp { address: String },
+    /// A WiFi mount or adapter. The port defaults to [udp::DEFAULT_UDP_PORT].
+    Udp { address: String },
+}
+
+/// How commands are retried after a communication error, see [RetryPolicy]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    #[serde(default)]
+    pub retry_delay_ms: u64,
+}
+
+/// The configuration of a mount
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MountConfig {
+    pub transport: Transport,
+    /// How long to wait for each reply
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub retry: Option<RetryConfig>,
+    /// Set on both axes after connecting
+    #[serde(default)]
+    pub autoguide_speed: Option<AutoGuideSpeed>,
+}
+
+fn default_baud_rate() -> u32 {
+    9600
+}
+
+fn default_timeout_ms() -> u64 {
+    1000
+}
+
+fn invalid(field: &str, reason: impl Into<String>) -> SynScanError {
+    SynScanError::InvalidConfig {
+        field: field.to_string(),
+        reason: reason.into(),
+    }
+}
+
+fn deserialize_error<E: std::fmt::Display>(e: serde_path_to_error::Error<E>) -> SynScanError {
+    invalid(&e.path().to_string(), e.inner().to_string())
+}
+
+impl MountConfig {
+    /// Parses and validates a TOML configuration
+    pub fn from_toml(s: &str) -> SynScanResult<MountConfig> {
+        let deserializer = toml::Deserializer::new(s);
+        let config: MountConfig =
+            serde_path_to_error::deserialize(deserializer).map_err(deserialize_error)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses and validates a JSON configuration
+    pub fn from_json(s: &str) -> SynScanResult<MountConfig> {
+        let mut deserializer = serde_json::Deserializer::from_str(s);
+        let config: MountConfig =
+            serde_path_to_error::deserialize(&mut deserializer).map_err(deserialize_error)?;
+        deserializer
+            .end()
+            .map_err(|e| invalid(".", e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reads a configuration file, choosing the format from its `.toml` or `.json` extension
+    pub fn load(path: impl AsRef<Path>) -> SynScanResult<MountConfig> {
+        let path = path.as_ref();
+        let file_error = |source| SynScanError::ConfigFileError {
+            path: path.to_path_buf(),
+            source,
+        };
+        let parse = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml,
+            Some("json") => Self::from_json,
+            _ => {
+                return Err(file_error(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "not a .toml or .json file",
+                )))
+            }
+        };
+        let contents = fs::read_to_string(path).map_err(file_error)?;
+        parse(&contents)
+    }
+
+    /// Checks values that parse but can't be used
+    pub fn validate(&self) -> SynScanResult<()> {
+        match &self.transport {
+            Transport::Serial { path, baud_rate } => {
+                if path.is_empty() {
+                    return Err(invalid("transport.serial.path", "must not be empty"));
+                }
+                if *baud_rate == 0 {
+                    return Err(invalid("transport.serial.baud_rate", "must not be zero"));
+                }
+            }
+            Transport::Tcp { address } if address.is_empty() => {
+                return Err(invalid("transport.tcp.address", "must not be empty"));
+            }
+            Transport::Udp { address } if address.is_empty() => {
+                return Err(invalid("transport.udp.address", "must not be empty"));
+            }
+            _ => {}
+        }
+        if self.timeout_ms == 0 {
+            return Err(invalid("timeout_ms", "must not be zero"));
+        }
+        Ok(())
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        match &self.retry {
+            Some(retry) => RetryPolicy::new(
+                retry.max_retries,
+                Duration::from_millis(retry.retry_delay_ms),
+            ),
+            None => RetryPolicy::none(),
+        }
+    }
+
+    /// Opens the configured transport without talking to the mount
+    pub fn open_port(&self) -> SynScanResult<BoxedSerialPort> {
+        let timeout = self.timeout();
+        Ok(match &self.transport {
+            Transport::Serial { path, baud_rate } => open_serial(path, *baud_rate, timeout)?,
+            Transport::Tcp { address } => Box::new(tcp::TcpSerialPort::connect(address, timeout)?),
+            Transport::Udp { address } => Box::new(UdpSerialPort::connect(
+                udp::with_default_port(address),
+                timeout,
+            )?),
+        })
+    }
+
+    /// Connects to the mount and applies the configured settings
+    pub fn connect(&self) -> SynScanResult<MotorController<BoxedSerialPort>> {
+        self.validate()?;
+        let mc = MotorController::new(self.open_port()?)?;
+        self.apply(&mc)?;
+        Ok(mc)
+    }
+
+    /// Applies the configured settings to an already connected mount
+    pub fn apply<T: SerialPort>(&self, mc: &MotorController<T>) -> SynScanResult<()> {
+        mc.set_retry_policy(self.retry_policy());
+        if let Some(speed) = self.autoguide_speed {
+            mc.set_autoguide_speed(MultiChannel::Both, speed)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serialport")]
+fn open_serial(path: &str, baud_rate: u32, timeout: Duration) -> SynScanResult<BoxedSerialPort> {
+    let port = ::serialport::new(path, baud_rate).timeout(timeout).open()?;
+    Ok(Box::new(port))
+}
+
+#[cfg(not(feature = "serialport"))]
+fn open_serial(_path: &str, _baud_rate: u32, _timeout: Duration) -> SynScanResult<BoxedSerialPort> {
+    Err(invalid(
+        "transport.serial",
+        "serial ports need the serialport feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests;
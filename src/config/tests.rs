@@ -0,0 +1,147 @@
+use super::*;
+use crate::port::commands::*;
+use crate::port::mock::MockSynScanPort;
+
+fn field_of(result: SynScanResult<MountConfig>) -> String {
+    match result.unwrap_err() {
+        SynScanError::InvalidConfig { field, .. } => field,
+        e => panic!("unexpected error {}", e),
+    }
+}
+
+#[test]
+fn test_config_from_toml() {
+    let config = MountConfig::from_toml(
+        r#"
+        autoguide_speed = "half"
+
+        [transport.serial]
+        path = "/dev/ttyUSB0"
+
+        [retry]
+        max_retries = 2
+        retry_delay_ms = 50
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config,
+        MountConfig {
+            transport: Transport::Serial {
+                path: "/dev/ttyUSB0".to_string(),
+                baud_rate: 9600,
+            },
+            timeout_ms: 1000,
+            retry: Some(RetryConfig {
+                max_retries: 2,
+                retry_delay_ms: 50,
+            }),
+            autoguide_speed: Some(AutoGuideSpeed::Half),
+        }
+    );
+    assert_eq!(
+        config.retry_policy(),
+        RetryPolicy::new(2, Duration::from_millis(50))
+    );
+}
+
+#[test]
+fn test_config_from_json() {
+    let config = MountConfig::from_json(
+        r#"{"transport": {"udp": {"address": "192.168.4.1"}}, "timeout_ms": 500}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.transport,
+        Transport::Udp {
+            address: "192.168.4.1".to_string()
+        }
+    );
+    assert_eq!(config.timeout(), Duration::from_millis(500));
+    assert_eq!(config.retry_policy(), RetryPolicy::none());
+    assert_eq!(config.autoguide_speed, None);
+}
+
+#[test]
+fn test_config_errors_name_field() {
+    assert_eq!(
+        field_of(MountConfig::from_toml(
+            "[transport.serial]\npath = \"/dev/ttyUSB0\"\nbaud_rate = \"fast\"\n"
+        )),
+        "transport.serial.baud_rate"
+    );
+    assert_eq!(
+        field_of(MountConfig::from_json(
+            r#"{"transport": {"tcp": {"address": "localhost:4030"}}, "autoguide_speed": "double"}"#
+        )),
+        "autoguide_speed"
+    );
+    assert_eq!(
+        field_of(MountConfig::from_json(
+            r#"{"transport": {"serial": {"path": "/dev/ttyUSB0", "baud_rate": 0}}}"#
+        )),
+        "transport.serial.baud_rate"
+    );
+    assert_eq!(
+        field_of(MountConfig::from_toml(
+            "timeout_ms = 0\n[transport.tcp]\naddress = \"localhost:4030\"\n"
+        )),
+        "timeout_ms"
+    );
+    // Misspelled fields aren't silently ignored
+    let error = MountConfig::from_toml(
+        "[transport.tcp]\naddress = \"localhost:4030\"\n[retry]\nmax_retires = 2\n",
+    )
+    .unwrap_err();
+    match error {
+        SynScanError::InvalidConfig { field, reason } => {
+            assert_eq!(field, "retry.max_retires");
+            assert!(reason.contains("max_retires"));
+        }
+        e => panic!("unexpected error {}", e),
+    }
+}
+
+#[test]
+fn test_config_apply() {
+    let config = MountConfig::from_json(
+        r#"{"transport": {"tcp": {"address": "localhost:4030"}},
+            "retry": {"max_retries": 3},
+            "autoguide_speed": "quarter"}"#,
+    )
+    .unwrap();
+
+    let mock = MockSynScanPort::new();
+    mock.add_ok();
+    for number in [b"1E4F02", b"B40000", b"E80300", b"100000", b"020000"] {
+        mock.add_valid_response(number);
+    }
+    let mc = MotorController::new(mock.clone()).unwrap();
+    mock.check_written(b":F3\r:a1\r:a2\r:b1\r:g1\r:g2\r");
+
+    config.apply(&mc).unwrap();
+    assert_eq!(mc.get_retry_policy(), RetryPolicy::new(3, Duration::ZERO));
+    mock.check_correct_query_written(SET_AUTOGUIDE_SPEED, MultiChannel::Both, b"3");
+}
+
+#[test]
+fn test_config_load_errors_name_file() {
+    let path = std::env::temp_dir().join("synscan-missing-config.toml");
+    match MountConfig::load(&path).unwrap_err() {
+        SynScanError::ConfigFileError {
+            path: error_path,
+            source,
+        } => {
+            assert_eq!(error_path, path);
+            assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        }
+        e => panic!("unexpected error {}", e),
+    }
+    match MountConfig::load("mount.yaml").unwrap_err() {
+        SynScanError::ConfigFileError { path, source } => {
+            assert_eq!(path, std::path::Path::new("mount.yaml"));
+            assert_eq!(source.kind(), std::io::ErrorKind::InvalidInput);
+        }
+        e => panic!("unexpected error {}", e),
+    }
+}
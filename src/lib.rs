@@ -1,3 +1,5 @@
+#[cfg(feature = "config")]
+pub mod config;
 mod motor_controller;
 mod port;
 
@@ -11,6 +13,11 @@ pub mod util {
 
 pub use motor_controller::*;
 pub use port::channels::*;
+pub use port::record;
+pub use port::tcp;
+pub use port::udp;
+pub use port::BoxedSerialPort;
+pub use port::RetryPolicy;
 pub use port::SerialPort;
 
 #[cfg(feature = "serialport")]
@@ -0,0 +1,120 @@
+use crate::port::commands::*;
+use crate::util::*;
+use crate::*;
+use std::ops::Range;
+
+/// Low level access to the motor controller registers.
+/// The register layout is undocumented and varies between motor boards.
+/// Writing registers can leave the controller in a bad state, so these are only meant for debugging.
+impl<T: SerialPort> MotorController<T> {
+    /// Sets the address used by subsequent register reads and writes
+    pub fn set_register_address(&self, channel: impl Channel, address: u32) -> SynScanResult<()> {
+        self.port
+            .send_cmd_number(SET_REGISTER_ADDRESS, channel, address, 6)
+    }
+
+    /// Writes a value to the register at the previously set address
+    pub fn set_register_value(&self, channel: impl Channel, value: u32) -> SynScanResult<()> {
+        self.port
+            .send_cmd_number(SET_REGISTER_VALUE, channel, value, 6)
+    }
+
+    /// Reads the value of the register at the previously set address
+    pub fn inquire_register_value(&self, channel: SingleChannel) -> SynScanResult<u32> {
+        self.port.inquire_number(INQUIRE_REGISTER_VALUE, channel)
+    }
+
+    /// Reads the value of the register at the given address
+    pub fn inquire_register(&self, channel: SingleChannel, address: u32) -> SynScanResult<u32> {
+        self.set_register_address(channel, address)?;
+        self.inquire_register_value(channel)
+    }
+
+    /// Writes a value to the register at the given address
+    pub fn set_register(
+        &self,
+        channel: impl Channel + Copy,
+        address: u32,
+        value: u32,
+    ) -> SynScanResult<()> {
+        self.set_register_address(channel, address)?;
+        self.set_register_value(channel, value)
+    }
+
+    /// Reads every register in the given address range, returning (address, value) pairs
+    pub fn dump_registers(
+        &self,
+        channel: SingleChannel,
+        addresses: Range<u32>,
+    ) -> SynScanResult<Vec<(u32, u32)>> {
+        addresses
+            .map(|address| Ok((address, self.inquire_register(channel, address)?)))
+            .collect()
+    }
+
+    /// Restarts the motor controller into its firmware bootloader.
+    /// The controller restarts without a reliable reply, so none is waited for.
+    ///
+    /// Once in bootloader mode the controller no longer responds to motor commands, and this
+    /// library can't upload firmware or leave the bootloader. Power cycling the mount returns
+    /// it to normal operation.
+    pub fn run_bootloader_mode(&self, channel: impl Channel) -> SynScanResult<()> {
+        self.port
+            .send_cmd_without_reply(RUN_BOOTLOADER_MODE, channel)
+    }
+
+    /// Sets the address used by subsequent EEPROM reads and writes
+    pub fn set_eeprom_address(&self, channel: impl Channel, address: u32) -> SynScanResult<()> {
+        self.port
+            .send_cmd_number(SET_EEPROM_ADDRESS, channel, address, 6)
+    }
+
+    /// Writes a byte to the EEPROM at the previously set address
+    pub fn set_eeprom_value(&self, channel: impl Channel, value: u8) -> SynScanResult<()> {
+        self.port
+            .send_cmd_number(SET_EEPROM_VALUE, channel, value as u32, 2)
+    }
+
+    /// Reads the EEPROM at the previously set address
+    pub fn inquire_eeprom_value(&self, channel: SingleChannel) -> SynScanResult<u32> {
+        self.port.inquire_number(INQUIRE_EEPROM_VALUE, channel)
+    }
+
+    /// Reads the EEPROM at the given address
+    pub fn inquire_eeprom(&self, channel: SingleChannel, address: u32) -> SynScanResult<u32> {
+        self.set_eeprom_address(channel, address)?;
+        self.inquire_eeprom_value(channel)
+    }
+
+    /// Writes a byte to the EEPROM at the given address
+    pub fn set_eeprom(
+        &self,
+        channel: impl Channel + Copy,
+        address: u32,
+        value: u8,
+    ) -> SynScanResult<()> {
+        self.set_eeprom_address(channel, address)?;
+        self.set_eeprom_value(channel, value)
+    }
+
+    /// Reads every EEPROM address in the given range, returning (address, value) pairs
+    pub fn dump_eeprom(
+        &self,
+        channel: SingleChannel,
+        addresses: Range<u32>,
+    ) -> SynScanResult<Vec<(u32, u32)>> {
+        addresses
+            .map(|address| Ok((address, self.inquire_eeprom(channel, address)?)))
+            .collect()
+    }
+
+    /// Sends an arbitrary command with the given payload and returns the data in the reply
+    pub fn send_raw_cmd(
+        &self,
+        cmd: u8,
+        channel: impl Channel,
+        payload: &[u8],
+    ) -> SynScanResult<Vec<u8>> {
+        self.port.raw_send_cmd(cmd, channel, payload)
+    }
+}
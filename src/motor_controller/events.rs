@@ -0,0 +1,59 @@
+use crate::*;
+
+/// How far in degrees from its target a goto can stop and still count as finished
+pub const GOTO_TOLERANCE_DEGREES: f64 = 0.05;
+
+/// A change in the mount noticed by a [MountMonitor]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MountEvent {
+    /// The axis started moving in goto mode
+    GotoStarted(SingleChannel),
+    /// The axis stopped moving in goto mode at its target
+    GotoFinished(SingleChannel),
+    /// The axis stopped moving in goto mode short of its target, because it was stopped or stalled
+    GotoAborted(SingleChannel),
+    /// The axis stopped moving in tracking mode.
+    /// Tracking has no target, so this includes stops that were asked for.
+    TrackingStopped(SingleChannel),
+    /// The controller reported the motor as blocked
+    Blocked(SingleChannel),
+    /// The moving axis now turns in the given direction
+    DirectionChanged(SingleChannel, Direction),
+    /// Polling the mount failed after previously succeeding
+    CommunicationLost,
+    /// Polling the mount succeeded after previously failing
+    CommunicationRestored,
+}
+
+impl MountEvent {
+    /// Returns the events implied by the mount going from one state to the next
+    pub fn between(prev: &MountState, next: &MountState) -> Vec<MountEvent> {
+        let mut events = Vec::new();
+        for channel in SingleChannel::VALUES {
+            let off_target = (next.pos_degrees[channel] - next.goto_target_degrees[channel]).abs()
+                > GOTO_TOLERANCE_DEGREES;
+            let (prev, next) = (prev.status[channel], next.status[channel]);
+
+            let started = next.running && (!prev.running || prev.mode != next.mode);
+            let stopped = prev.running && (!next.running || prev.mode != next.mode);
+
+            if stopped {
+                events.push(match prev.mode {
+                    DriveMode::Goto if off_target => MountEvent::GotoAborted(channel),
+                    DriveMode::Goto => MountEvent::GotoFinished(channel),
+                    DriveMode::Tracking => MountEvent::TrackingStopped(channel),
+                });
+            }
+            if started && next.mode == DriveMode::Goto {
+                events.push(MountEvent::GotoStarted(channel));
+            }
+            if next.blocked && !prev.blocked {
+                events.push(MountEvent::Blocked(channel));
+            }
+            if prev.running && next.running && prev.direction != next.direction {
+                events.push(MountEvent::DirectionChanged(channel, next.direction));
+            }
+        }
+        events
+    }
+}
@@ -1,4 +1,8 @@
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod events;
 mod goto;
+mod monitor;
 mod motion_rate;
 mod pos;
 mod status;
@@ -7,11 +11,13 @@ mod types {
     mod autoguide_speed;
     mod direction;
     mod drive_mode;
+    mod motor_board_version;
     mod motor_parameters;
 
     pub use autoguide_speed::*;
     pub use direction::*;
     pub use drive_mode::*;
+    pub use motor_board_version::*;
     pub use motor_parameters::*;
 }
 
@@ -22,19 +28,30 @@ use crate::port::commands::*;
 use crate::port::SynScanPort;
 use crate::util::*;
 use crate::*;
+use std::sync::Arc;
 
-pub use goto::*;
-pub use motion_rate::*;
-pub use pos::*;
+pub use events::*;
+pub use monitor::*;
 pub use status::*;
 pub use types::*;
 
-/// A MotorController is a handle for controlling the SkyWatcher mount through a serial port
+/// A MotorController is a handle for controlling the SkyWatcher mount through a serial port.
+/// Clones are cheap and share the same port, so one mount can be driven from several threads.
+/// Commands from different clones are sent one at a time.
 pub struct MotorController<T: SerialPort> {
-    port: SynScanPort<T>,
+    port: Arc<SynScanPort<T>>,
     motor_parameters: MotorParameters,
 }
 
+impl<T: SerialPort> Clone for MotorController<T> {
+    fn clone(&self) -> Self {
+        Self {
+            port: Arc::clone(&self.port),
+            motor_parameters: self.motor_parameters,
+        }
+    }
+}
+
 impl<T> MotorController<T>
 where
     T: SerialPort,
@@ -46,7 +63,7 @@ where
 
         let motor_parameters = port.get_motor_parameters()?;
         Ok(Self {
-            port,
+            port: Arc::new(port),
             motor_parameters,
         })
     }
@@ -63,6 +80,25 @@ impl<T: SerialPort> MotorController<T> {
         self.port.test()
     }
 
+    /// Reads the firmware version and mount model of the motor board
+    pub fn inquire_motor_board_version(
+        &self,
+        channel: SingleChannel,
+    ) -> SynScanResult<MotorBoardVersion> {
+        self.port.inquire_motor_board_version(channel)
+    }
+
+    /// Returns the policy used to retry commands after communication errors
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        self.port.get_retry_policy()
+    }
+
+    /// Sets the policy used to retry commands after communication errors.
+    /// By default commands are not retried.
+    pub fn set_retry_policy(&self, retry_policy: RetryPolicy) {
+        self.port.set_retry_policy(retry_policy)
+    }
+
     /// Sets the autoguide speed of the mount
     pub fn set_autoguide_speed(
         &self,
@@ -72,4 +108,10 @@ impl<T: SerialPort> MotorController<T> {
         self.port
             .send_cmd_bytes(SET_AUTOGUIDE_SPEED, channel, &[speed.comm_byte()])
     }
+
+    /// Enables or disables the controller's debug output
+    pub fn set_debug_flag(&self, channel: impl Channel, enabled: bool) -> SynScanResult<()> {
+        let flag = if enabled { b'1' } else { b'0' };
+        self.port.send_cmd_bytes(SET_DEBUG_FLAG, channel, &[flag])
+    }
 }
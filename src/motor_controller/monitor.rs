@@ -0,0 +1,180 @@
+use crate::util::*;
+use crate::*;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// A snapshot of both axes of the mount
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MountState {
+    /// When the mount was polled
+    pub timestamp: SystemTime,
+    /// Position in counts relative to initialization
+    pub pos: BiChannelValue<i32>,
+    /// Position in degrees relative to initialization
+    pub pos_degrees: BiChannelValue<f64>,
+    pub status: BiChannelValue<MotorStatus>,
+    /// Set motion rate in degrees per second. This can be non-zero even if the axis is stopped.
+    pub motion_rate_degrees: BiChannelValue<f64>,
+    /// Goto target in counts relative to initialization
+    pub goto_target: BiChannelValue<i32>,
+    /// Goto target in degrees relative to initialization
+    pub goto_target_degrees: BiChannelValue<f64>,
+}
+
+impl<T: SerialPort> MotorController<T> {
+    /// Polls the position, status, motion rate and goto target of both axes
+    pub fn inquire_state(&self) -> SynScanResult<MountState> {
+        let timestamp = SystemTime::now();
+        let pos = BiChannelValue::new_from_result_fn(|c| self.inquire_pos(c))?;
+        let status = BiChannelValue::new_from_result_fn(|c| self.inquire_status(c))?;
+        let step_period = BiChannelValue::new_from_result_fn(|c| self.inquire_step_period(c))?;
+        let goto_target = BiChannelValue::new_from_result_fn(|c| self.inquire_goto_target(c))?;
+        Ok(MountState {
+            timestamp,
+            pos,
+            pos_degrees: BiChannelValue::new_from_fn(|c| {
+                self.motor_parameters.counts_to_degrees(c, pos[c] as f64)
+            }),
+            status,
+            motion_rate_degrees: BiChannelValue::new_from_fn(|c| {
+                let counts_per_sec = self.motor_parameters.step_period_to_counts_per_sec(
+                    c,
+                    status[c].fast,
+                    step_period[c],
+                );
+                self.motor_parameters.counts_to_degrees(c, counts_per_sec)
+            }),
+            goto_target,
+            goto_target_degrees: BiChannelValue::new_from_fn(|c| {
+                self.motor_parameters
+                    .counts_to_degrees(c, goto_target[c] as f64)
+            }),
+        })
+    }
+}
+
+struct Subscribers {
+    latest: Option<MountState>,
+    connected: bool,
+    senders: Vec<Sender<MountState>>,
+    event_senders: Vec<Sender<MountEvent>>,
+}
+
+impl Subscribers {
+    fn publish_event(&mut self, event: MountEvent) {
+        self.event_senders
+            .retain(|sender| sender.send(event).is_ok());
+    }
+
+    /// Publishes the result of a poll along with any events it implies
+    fn publish(&mut self, result: SynScanResult<MountState>) {
+        let state = match result {
+            Ok(state) => state,
+            Err(_) => {
+                if self.connected {
+                    self.connected = false;
+                    self.publish_event(MountEvent::CommunicationLost);
+                }
+                return;
+            }
+        };
+
+        if !self.connected {
+            self.connected = true;
+            self.publish_event(MountEvent::CommunicationRestored);
+        }
+        if let Some(prev) = self.latest {
+            for event in MountEvent::between(&prev, &state) {
+                self.publish_event(event);
+            }
+        }
+        self.latest = Some(state);
+        self.senders.retain(|sender| sender.send(state).is_ok());
+    }
+}
+
+/// Polls the mount on a background thread and publishes each [MountState] to its subscribers.
+/// Changes between states are also published as [MountEvent]s.
+/// Polls that fail are skipped. The thread is stopped when the monitor is dropped.
+pub struct MountMonitor {
+    subscribers: Arc<Mutex<Subscribers>>,
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MountMonitor {
+    /// Starts polling the mount every interval
+    pub fn new<T>(mc: MotorController<T>, interval: Duration) -> MountMonitor
+    where
+        T: SerialPort + Send + 'static,
+    {
+        let subscribers = Arc::new(Mutex::new(Subscribers {
+            latest: None,
+            connected: true,
+            senders: Vec::new(),
+            event_senders: Vec::new(),
+        }));
+        let (stop, stop_rx) = mpsc::channel();
+
+        let thread_subscribers = Arc::clone(&subscribers);
+        let handle = thread::spawn(move || loop {
+            let result = mc.inquire_state();
+            thread_subscribers.lock().unwrap().publish(result);
+
+            match stop_rx.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+        });
+
+        MountMonitor {
+            subscribers,
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+
+    /// Returns a receiver for every state polled from now on.
+    /// The most recent state, if any, is sent immediately.
+    pub fn subscribe(&self) -> Receiver<MountState> {
+        let (sender, receiver) = mpsc::channel();
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if let Some(state) = subscribers.latest {
+            sender.send(state).unwrap();
+        }
+        subscribers.senders.push(sender);
+        receiver
+    }
+
+    /// Returns a receiver for every event noticed from now on
+    pub fn subscribe_events(&self) -> Receiver<MountEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().event_senders.push(sender);
+        receiver
+    }
+
+    /// Returns the most recently polled state
+    pub fn latest(&self) -> Option<MountState> {
+        self.subscribers.lock().unwrap().latest
+    }
+}
+
+impl Drop for MountMonitor {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the thread
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl<T: SerialPort + Send + 'static> MotorController<T> {
+    /// Starts a [MountMonitor] polling this mount every interval
+    pub fn monitor(&self, interval: Duration) -> MountMonitor {
+        MountMonitor::new(self.clone(), interval)
+    }
+}
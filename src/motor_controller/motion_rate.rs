@@ -36,8 +36,10 @@ impl<T: SerialPort> MotorController<T> {
         channel: SingleChannel,
         step_period: u32,
     ) -> SynScanResult<f64> {
-        Ok(self.determine_motion_rate_multiplier(channel)?
-            * (self.motor_parameters.timer_interrupt_freq as f64 / step_period as f64))
+        let fast = self.inquire_status(channel)?.fast;
+        Ok(self
+            .motor_parameters
+            .step_period_to_counts_per_sec(channel, fast, step_period))
     }
 
     /// Calculates the set motion rate of the mount in counts per second
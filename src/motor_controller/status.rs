@@ -3,9 +3,9 @@ use crate::util::*;
 use crate::Direction::*;
 use crate::DriveMode::*;
 use crate::*;
-use std::io;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MotorStatus {
     pub mode: DriveMode,
     pub direction: Direction,
@@ -16,29 +16,24 @@ pub struct MotorStatus {
     pub level_switch: bool,
 }
 
-impl<T: SerialPort> MotorController<T> {
-    /// Returns a [MotorStatus] describing the mount status
-    pub fn inquire_status(&self, channel: SingleChannel) -> SynScanResult<MotorStatus> {
-        let data = self.port.inquire_bytes(INQUIRE_STATUS, channel)?;
+impl MotorStatus {
+    /// Parses the response to the status inquiry
+    fn from_bytes(data: &[u8]) -> SynScanResult<MotorStatus> {
         let bytes = data
-            .into_iter()
-            .map(|b| {
-                if (b'0'..=b'9').contains(&b) {
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_digit() {
                     Ok(b - b'0')
                 } else if (b'A'..=b'F').contains(&b) {
                     Ok(b - b'A')
                 } else {
-                    Err(SynScanError::CommunicationError(io::Error::from(
-                        io::ErrorKind::InvalidData,
-                    )))
+                    Err(SynScanError::InvalidResponse)
                 }
             })
             .collect::<SynScanResult<Vec<u8>>>()?;
 
         if bytes.len() != 3 {
-            return Err(SynScanError::CommunicationError(io::Error::from(
-                io::ErrorKind::InvalidData,
-            )));
+            return Err(SynScanError::InvalidResponse);
         }
 
         Ok(MotorStatus {
@@ -59,6 +54,14 @@ impl<T: SerialPort> MotorController<T> {
             level_switch: bytes[2] & 0x2 != 0,
         })
     }
+}
+
+impl<T: SerialPort> MotorController<T> {
+    /// Returns a [MotorStatus] describing the mount status
+    pub fn inquire_status(&self, channel: SingleChannel) -> SynScanResult<MotorStatus> {
+        self.port
+            .inquire_parsed(INQUIRE_STATUS, channel, MotorStatus::from_bytes)
+    }
 
     /// Sets the motion mode to either fast or slow GOTO mode
     /// Errors if called when the mount is not stopped
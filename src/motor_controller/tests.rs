@@ -1,9 +1,11 @@
-use std::sync::Mutex;
 use crate::port::mock::MockSynScanPort;
 use crate::port::SynScanPort;
 use crate::util::*;
 use crate::Direction::*;
 use crate::*;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 use crate::port::commands::*;
 use crate::MultiChannel::*;
@@ -17,7 +19,7 @@ fn get_mc<T: SerialPort>(mock: T, params: Option<MotorParameters>) -> MotorContr
     });
 
     MotorController {
-        port: SynScanPort(Mutex::new(mock)),
+        port: Arc::new(SynScanPort::new(mock)),
         motor_parameters: params,
     }
 }
@@ -40,7 +42,7 @@ fn test_set_motion_mode() {
     let mc = get_mc(mock.clone(), None);
     mc.set_motion_mode(SingleChannel::Channel1, DriveMode::Goto, true, Clockwise)
         .unwrap();
-    mock.check_correct_query_written(SET_MOTION_MODE, Channel1, &[b'0', b'0']);
+    mock.check_correct_query_written(SET_MOTION_MODE, Channel1, b"00");
     mc.set_motion_mode(
         SingleChannel::Channel2,
         DriveMode::Tracking,
@@ -48,7 +50,7 @@ fn test_set_motion_mode() {
         CounterClockwise,
     )
     .unwrap();
-    mock.check_correct_query_written(SET_MOTION_MODE, Channel2, &[b'3', b'1']);
+    mock.check_correct_query_written(SET_MOTION_MODE, Channel2, b"31");
 }
 
 #[test]
@@ -89,7 +91,17 @@ fn test_set_autoguide_speed() {
     let mc = get_mc(mock.clone(), None);
     mc.set_autoguide_speed(SingleChannel::Channel1, AutoGuideSpeed::ThreeQuarters)
         .unwrap();
-    mock.check_correct_query_written(SET_AUTOGUIDE_SPEED, Channel1, &[b'1'])
+    mock.check_correct_query_written(SET_AUTOGUIDE_SPEED, Channel1, b"1")
+}
+
+#[test]
+fn test_set_debug_flag() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+    mc.set_debug_flag(Both, true).unwrap();
+    mock.check_correct_query_written(SET_DEBUG_FLAG, Both, b"1");
+    mc.set_debug_flag(Channel2, false).unwrap();
+    mock.check_correct_query_written(SET_DEBUG_FLAG, Channel2, b"0");
 }
 
 #[test]
@@ -109,6 +121,22 @@ fn test_get_goto_target() {
     mock.check_correct(INQUIRE_GOTO_TARGET_POSITION, Channel2);
 }
 
+#[test]
+fn test_get_motor_board_version() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+    mock.add_valid_response(b"032604");
+    assert_eq!(
+        mc.inquire_motor_board_version(Channel1).unwrap(),
+        MotorBoardVersion {
+            major: 0x03,
+            minor: 0x26,
+            mount_code: 0x04,
+        }
+    );
+    mock.check_correct(INQUIRE_MOTOR_BOARD_VERSION, Channel1);
+}
+
 #[test]
 fn test_get_step_period() {
     let mock = MockSynScanPort::new();
@@ -138,7 +166,7 @@ fn test_get_pos() {
 fn test_get_status() {
     let mock = MockSynScanPort::new();
     let mc = get_mc(mock.clone(), None);
-    mock.add_valid_response(&[b'7', b'1', b'1']);
+    mock.add_valid_response(b"711");
     assert_eq!(
         mc.inquire_status(Channel1).unwrap(),
         MotorStatus {
@@ -153,7 +181,7 @@ fn test_get_status() {
     );
     mock.check_correct(INQUIRE_STATUS, Channel1);
 
-    mock.add_valid_response(&[b'0', b'2', b'3']);
+    mock.add_valid_response(b"023");
     assert_eq!(
         mc.inquire_status(SingleChannel::Channel2).unwrap(),
         MotorStatus {
@@ -168,7 +196,7 @@ fn test_get_status() {
     );
     mock.check_correct(INQUIRE_STATUS, Channel2);
 
-    mock.add_valid_response(&[b'7', b'3', b'3']);
+    mock.add_valid_response(b"733");
     assert_eq!(
         mc.inquire_status(Channel2).unwrap(),
         MotorStatus {
@@ -183,3 +211,345 @@ fn test_get_status() {
     );
     mock.check_correct(INQUIRE_STATUS, Channel2);
 }
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn test_registers() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+
+    mc.set_register_address(Channel1, 0x12).unwrap();
+    mock.check_correct_number_written(SET_REGISTER_ADDRESS, Channel1, 0x12, 6);
+
+    mc.set_register_value(Both, 0xABCD).unwrap();
+    mock.check_correct_number_written(SET_REGISTER_VALUE, Both, 0xABCD, 6);
+
+    mock.add_valid_number(0x3456, 4);
+    assert_eq!(mc.inquire_register_value(Channel2).unwrap(), 0x3456);
+    mock.check_correct(INQUIRE_REGISTER_VALUE, Channel2);
+}
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn test_dump_registers() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+
+    mock.add_ok();
+    mock.add_valid_number(0x11, 2);
+    mock.add_ok();
+    mock.add_valid_number(0x22, 2);
+    assert_eq!(
+        mc.dump_registers(Channel1, 4..6).unwrap(),
+        vec![(4, 0x11), (5, 0x22)]
+    );
+    mock.check_written(b":A1040000\r:r1\r:A1050000\r:r1\r");
+}
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn test_eeprom() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+
+    mc.set_eeprom_address(Channel1, 0x40).unwrap();
+    mock.check_correct_number_written(SET_EEPROM_ADDRESS, Channel1, 0x40, 6);
+
+    mc.set_eeprom_value(Channel1, 0xA5).unwrap();
+    mock.check_correct_number_written(SET_EEPROM_VALUE, Channel1, 0xA5, 2);
+
+    mock.add_valid_number(0x5A, 2);
+    assert_eq!(mc.inquire_eeprom_value(Channel2).unwrap(), 0x5A);
+    mock.check_correct(INQUIRE_EEPROM_VALUE, Channel2);
+
+    mock.add_ok();
+    mock.add_valid_number(0x11, 2);
+    mock.add_ok();
+    mock.add_valid_number(0x22, 2);
+    assert_eq!(
+        mc.dump_eeprom(Channel1, 0..2).unwrap(),
+        vec![(0, 0x11), (1, 0x22)]
+    );
+}
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn test_send_raw_cmd() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+    mock.add_valid_response(b"0A0B");
+    assert_eq!(
+        mc.send_raw_cmd(b'q', Channel1, b"010000").unwrap(),
+        b"0A0B"
+    );
+    mock.check_correct_query_written(b'q', Channel1, b"010000");
+}
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn test_run_bootloader_mode() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+    mock.add_valid_number(0x2B, 2);
+    mc.run_bootloader_mode(Both).unwrap();
+    mock.check_correct(RUN_BOOTLOADER_MODE, Both);
+    // No reply was read
+    assert_eq!(mc.inquire_step_period(Channel1).unwrap(), 0x2B);
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send_sync::<MotorController<MockSynScanPort>>();
+    assert_send_sync::<MotorController<tcp::TcpSerialPort>>();
+}
+
+#[test]
+fn test_shared_between_threads() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let mc = mc.clone();
+            thread::spawn(move || {
+                for _ in 0..25 {
+                    mc.stop_motion(Channel1).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // Every command is written whole, without interleaving
+    mock.check_written(&b":K1\r".repeat(100));
+}
+
+fn add_state_responses(mock: &MockSynScanPort) {
+    mock.add_valid_number(0x800000 + 25, 6);
+    mock.add_valid_number(0x800000 - 45, 6);
+    mock.add_valid_response(b"511");
+    mock.add_valid_response(b"201");
+    mock.add_valid_number(1000, 6);
+    mock.add_valid_number(250, 6);
+    mock.add_valid_number(0x800000 + 25, 6);
+    mock.add_valid_number(0x800000 + 90, 6);
+}
+
+#[test]
+fn test_inquire_state() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+    add_state_responses(&mock);
+
+    let state = mc.inquire_state().unwrap();
+    assert_eq!(state.pos[Channel1], 25);
+    assert_eq!(state.pos_degrees[Channel2], -90.);
+    assert!(state.status[Channel1].fast);
+    assert!(state.status[Channel1].running);
+    assert!(!state.status[Channel2].running);
+    // 16 * 1000 / 1000 counts per second
+    assert_eq!(
+        state.motion_rate_degrees[Channel1],
+        mc.get_motor_parameters().counts_to_degrees(Channel1, 16.)
+    );
+    // 1000 / 250 counts per second
+    assert_eq!(state.motion_rate_degrees[Channel2], 8.);
+    assert_eq!(state.goto_target[Channel1], 25);
+    assert_eq!(state.goto_target_degrees[Channel2], 180.);
+}
+
+#[test]
+fn test_monitor() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+    add_state_responses(&mock);
+
+    let monitor = mc.monitor(Duration::from_millis(10));
+    let subscriber = monitor.subscribe();
+    let state = subscriber.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(state.pos[Channel1], 25);
+    assert_eq!(monitor.latest().unwrap().pos[Channel2], -45);
+
+    // Polls fail once the responses run out, so nothing else is published
+    assert!(subscriber
+        .recv_timeout(Duration::from_millis(50))
+        .is_err());
+
+    drop(monitor);
+    assert!(subscriber.recv().is_err());
+}
+
+fn state_with_status(status: [MotorStatus; 2]) -> MountState {
+    MountState {
+        timestamp: SystemTime::now(),
+        pos: BiChannelValue::new(0, 0),
+        pos_degrees: BiChannelValue::new(0., 0.),
+        status: BiChannelValue::new(status[0], status[1]),
+        motion_rate_degrees: BiChannelValue::new(0., 0.),
+        goto_target: BiChannelValue::new(0, 0),
+        goto_target_degrees: BiChannelValue::new(0., 0.),
+    }
+}
+
+#[test]
+fn test_events_between_states() {
+    let stopped = MotorStatus {
+        mode: DriveMode::Goto,
+        direction: Clockwise,
+        fast: false,
+        running: false,
+        blocked: false,
+        inited: true,
+        level_switch: false,
+    };
+    let goto = MotorStatus {
+        running: true,
+        ..stopped
+    };
+    let tracking = MotorStatus {
+        mode: DriveMode::Tracking,
+        ..goto
+    };
+
+    let events = |prev, next| {
+        MountEvent::between(&state_with_status(prev), &state_with_status(next))
+    };
+
+    assert_eq!(events([stopped, stopped], [stopped, stopped]), vec![]);
+    assert_eq!(
+        events([stopped, tracking], [goto, tracking]),
+        vec![MountEvent::GotoStarted(Channel1)]
+    );
+    assert_eq!(
+        events([goto, goto], [stopped, tracking]),
+        vec![
+            MountEvent::GotoFinished(Channel1),
+            MountEvent::GotoFinished(Channel2)
+        ]
+    );
+
+    // A goto that stops short of its target was aborted
+    let short_of_target = MountState {
+        pos_degrees: BiChannelValue::new(10., 0.02),
+        goto_target_degrees: BiChannelValue::new(20., 0.),
+        ..state_with_status([stopped, stopped])
+    };
+    assert_eq!(
+        MountEvent::between(&state_with_status([goto, goto]), &short_of_target),
+        vec![
+            MountEvent::GotoAborted(Channel1),
+            MountEvent::GotoFinished(Channel2)
+        ]
+    );
+
+    assert_eq!(
+        events(
+            [tracking, tracking],
+            [
+                MotorStatus {
+                    running: false,
+                    blocked: true,
+                    ..tracking
+                },
+                MotorStatus {
+                    direction: CounterClockwise,
+                    ..tracking
+                }
+            ]
+        ),
+        vec![
+            MountEvent::TrackingStopped(Channel1),
+            MountEvent::Blocked(Channel1),
+            MountEvent::DirectionChanged(Channel2, CounterClockwise)
+        ]
+    );
+}
+
+#[test]
+fn test_monitor_events() {
+    let mock = MockSynScanPort::new();
+    let mc = get_mc(mock.clone(), None);
+    add_state_responses(&mock);
+    // Channel 1 has stopped tracking
+    mock.add_valid_number(0x800000 + 25, 6);
+    mock.add_valid_number(0x800000 - 45, 6);
+    mock.add_valid_response(b"401");
+    mock.add_valid_response(b"201");
+    mock.add_valid_number(1000, 6);
+    mock.add_valid_number(250, 6);
+    mock.add_valid_number(0x800000 + 25, 6);
+    mock.add_valid_number(0x800000 + 90, 6);
+
+    let monitor = MountMonitor::new(mc, Duration::from_millis(100));
+    let events = monitor.subscribe_events();
+    assert_eq!(
+        events.recv_timeout(Duration::from_secs(1)).unwrap(),
+        MountEvent::TrackingStopped(Channel1)
+    );
+    // Polls fail once the responses run out
+    assert_eq!(
+        events.recv_timeout(Duration::from_secs(1)).unwrap(),
+        MountEvent::CommunicationLost
+    );
+}
+
+#[cfg(feature = "serde")]
+fn round_trip<T>(value: &T, expected_json: &str) -> T
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let json = serde_json::to_string(value).unwrap();
+    assert_eq!(json, expected_json);
+    serde_json::from_str(&json).unwrap()
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    assert_eq!(round_trip(&Clockwise, r#""clockwise""#), Clockwise);
+    assert_eq!(
+        round_trip(&CounterClockwise, r#""counter_clockwise""#),
+        CounterClockwise
+    );
+    assert_eq!(round_trip(&DriveMode::Goto, r#""goto""#), DriveMode::Goto);
+    assert_eq!(
+        round_trip(&AutoGuideSpeed::ThreeQuarters, r#""three_quarters""#),
+        AutoGuideSpeed::ThreeQuarters
+    );
+
+    let status = MotorStatus {
+        mode: DriveMode::Tracking,
+        direction: CounterClockwise,
+        fast: false,
+        running: true,
+        blocked: false,
+        inited: true,
+        level_switch: false,
+    };
+    assert_eq!(
+        round_trip(
+            &status,
+            r#"{"mode":"tracking","direction":"counter_clockwise","fast":false,"running":true,"blocked":false,"inited":true,"level_switch":false}"#
+        ),
+        status
+    );
+
+    let params = MotorParameters {
+        counts_per_revolution: BiChannelValue::new(9024000, 9024001),
+        timer_interrupt_freq: 64935,
+        high_speed_ratio: BiChannelValue::new(32, 16),
+    };
+    let read = round_trip(
+        &params,
+        r#"{"counts_per_revolution":{"channel1":9024000,"channel2":9024001},"timer_interrupt_freq":64935,"high_speed_ratio":{"channel1":32,"channel2":16}}"#,
+    );
+    for c in SingleChannel::VALUES {
+        assert_eq!(read.counts_per_revolution[c], params.counts_per_revolution[c]);
+        assert_eq!(read.high_speed_ratio[c], params.high_speed_ratio[c]);
+    }
+    assert_eq!(read.timer_interrupt_freq, params.timer_interrupt_freq);
+}
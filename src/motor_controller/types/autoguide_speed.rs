@@ -1,6 +1,8 @@
 use std::cmp::Ordering;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum AutoGuideSpeed {
     One,
     ThreeQuarters,
@@ -33,7 +35,7 @@ impl AutoGuideSpeed {
 
 impl PartialOrd<Self> for AutoGuideSpeed {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.multiplier().partial_cmp(&other.multiplier())
+        Some(self.cmp(other))
     }
 }
 
@@ -0,0 +1,35 @@
+use crate::port::commands::*;
+use crate::port::SynScanPort;
+use crate::util::*;
+use crate::*;
+
+/// The firmware version and mount model reported by a motor board
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MotorBoardVersion {
+    pub major: u8,
+    pub minor: u8,
+    /// Identifies the mount model, e.g. 0x00 for an EQ6
+    pub mount_code: u8,
+}
+
+impl MotorBoardVersion {
+    /// Interprets the number returned by the version inquiry
+    pub(crate) fn from_number(number: u32) -> MotorBoardVersion {
+        MotorBoardVersion {
+            major: (number & 0xFF) as u8,
+            minor: ((number >> 8) & 0xFF) as u8,
+            mount_code: ((number >> 16) & 0xFF) as u8,
+        }
+    }
+}
+
+impl<T: SerialPort> SynScanPort<T> {
+    pub fn inquire_motor_board_version(
+        &self,
+        channel: SingleChannel,
+    ) -> SynScanResult<MotorBoardVersion> {
+        Ok(MotorBoardVersion::from_number(
+            self.inquire_number(INQUIRE_MOTOR_BOARD_VERSION, channel)?,
+        ))
+    }
+}
@@ -6,6 +6,7 @@ use crate::*;
 
 /// Constant motor parameters given by the mount
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MotorParameters {
     pub counts_per_revolution: BiChannelValue<u32>,
     pub timer_interrupt_freq: u32,
@@ -16,13 +17,28 @@ pub struct MotorParameters {
 impl MotorParameters {
     pub fn counts_to_degrees(&self, channel: SingleChannel, counts: f64) -> f64 {
         let counts_per_rev = self.counts_per_revolution[channel];
-        counts as f64 / counts_per_rev as f64 * 360.
+        counts / counts_per_rev as f64 * 360.
     }
 
     pub fn degrees_to_counts(&self, channel: SingleChannel, degrees: f64) -> f64 {
         let counts_per_rev = self.counts_per_revolution[channel];
         (degrees / 360.) * counts_per_rev as f64
     }
+
+    /// Calculates the motion rate in counts per second for a step period in fast or slow mode
+    pub fn step_period_to_counts_per_sec(
+        &self,
+        channel: SingleChannel,
+        fast: bool,
+        step_period: u32,
+    ) -> f64 {
+        let multiplier = if fast {
+            self.high_speed_ratio[channel] as f64
+        } else {
+            1.
+        };
+        multiplier * (self.timer_interrupt_freq as f64 / step_period as f64)
+    }
 }
 
 impl<T: SerialPort> SynScanPort<T> {
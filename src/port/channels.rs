@@ -15,7 +15,7 @@ impl Channel for MultiChannel {
 }
 
 /* SingleChannel */
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum SingleChannel {
     Channel1,
     Channel2,
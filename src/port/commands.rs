@@ -46,3 +46,35 @@ pub const INQUIRE_EEPROM_VALUE: u8 = b'n';
 pub const SET_REGISTER_ADDRESS: u8 = b'A';
 pub const SET_REGISTER_VALUE: u8 = b'R';
 pub const INQUIRE_REGISTER_VALUE: u8 = b'r';
+
+/// Whether the command can be resent after a communication error without changing its effect.
+/// This holds for inquiries and commands that set an absolute value.
+pub fn is_retry_safe(cmd: u8) -> bool {
+    matches!(
+        cmd,
+        INQUIRE_COUNTS_PER_REVOLUTION
+            | INQUIRE_TIMER_INTERRUPT_FREQUENCY
+            | INQUIRE_BRAKE_STEPS
+            | INQUIRE_GOTO_TARGET_POSITION
+            | INQUIRE_STEP_PERIOD
+            | INQUIRE_POSITION
+            | INQUIRE_INCREMENT
+            | INQUIRE_BRAKE_POINT
+            | INQUIRE_STATUS
+            | INQUIRE_HIGH_SPEED_RATIO
+            | INQUIRE_1X_TRACKING_PERIOD
+            | INQUIRE_TELE_AXIS_POSITION
+            | INQUIRE_MOTOR_BOARD_VERSION
+            | INQUIRE_PEC_PERIOD
+            | INQUIRE_EEPROM_VALUE
+            | INQUIRE_REGISTER_VALUE
+            | INITIALIZATION_DONE
+            | SET_POSITION
+            | SET_MOTION_MODE
+            | SET_GOTO_TARGET
+            | SET_STEP_PERIOD
+            | SET_AUTOGUIDE_SPEED
+            | STOP_MOTION
+            | INSTANT_STOP
+    )
+}
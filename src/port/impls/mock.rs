@@ -3,8 +3,7 @@
 use crate::port::commands::*;
 use crate::*;
 use std::io;
-use std::rc::Rc;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 struct MockBufs {
     bytes_written: Vec<u8>,
@@ -14,13 +13,13 @@ struct MockBufs {
 /// A mock implementation of a SynScan port where the response is manually given and the written bytes can be read
 #[derive(Clone)]
 pub struct MockSynScanPort {
-    bufs: Rc<Mutex<MockBufs>>,
+    bufs: Arc<Mutex<MockBufs>>,
 }
 
 impl MockSynScanPort {
     pub fn new() -> MockSynScanPort {
         MockSynScanPort {
-            bufs: Rc::new(Mutex::new(MockBufs {
+            bufs: Arc::new(Mutex::new(MockBufs {
                 bytes_written: Vec::with_capacity(40),
                 bytes_to_read: Vec::with_capacity(40),
             })),
@@ -55,7 +54,6 @@ impl MockSynScanPort {
     }
 
     pub fn check_correct_query_written(&self, command: u8, channel: impl Channel, buf: &[u8]) {
-        let mut bufs = self.bufs.lock().unwrap();
         let mut correct = Vec::with_capacity(buf.len() + 3);
         correct.push(QUERY_BYTE);
         correct.push(command);
@@ -63,7 +61,12 @@ impl MockSynScanPort {
         correct.extend_from_slice(buf);
         correct.push(TERMINATION_BYTE);
 
-        assert_eq!(&bufs.bytes_written, &correct);
+        self.check_written(&correct)
+    }
+
+    pub fn check_written(&self, buf: &[u8]) {
+        let mut bufs = self.bufs.lock().unwrap();
+        assert_eq!(&bufs.bytes_written, buf);
         bufs.bytes_written.clear();
     }
 }
@@ -78,11 +81,11 @@ impl io::Read for MockSynScanPort {
             bufs.bytes_to_read.push(TERMINATION_BYTE);
         }
 
-        for i in 0..buf.len() {
+        for (i, b) in buf.iter_mut().enumerate() {
             if bufs.bytes_to_read.is_empty() {
                 return Ok(i);
             } else {
-                buf[i] = bufs.bytes_to_read.remove(0);
+                *b = bufs.bytes_to_read.remove(0);
             }
         }
         Ok(buf.len())
@@ -0,0 +1,268 @@
+use crate::port::commands::TERMINATION_BYTE;
+use crate::SerialPort;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, LineWriter};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Whether a frame was sent to or received from the mount
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FrameDirection {
+    Written,
+    Read,
+}
+
+/// A single command or response passing through a port.
+/// The bytes don't include the termination byte.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordedFrame {
+    /// Time since recording started
+    pub elapsed: Duration,
+    pub direction: FrameDirection,
+    pub bytes: Vec<u8>,
+}
+
+impl RecordedFrame {
+    /// Formats the frame as a single line, e.g. `1042 > :j1`
+    pub fn to_line(&self) -> String {
+        let direction = match self.direction {
+            FrameDirection::Written => '>',
+            FrameDirection::Read => '<',
+        };
+        format!(
+            "{} {} {}",
+            self.elapsed.as_micros(),
+            direction,
+            self.bytes.escape_ascii()
+        )
+    }
+
+    /// Parses a line written by [RecordedFrame::to_line]
+    pub fn from_line(line: &str) -> io::Result<RecordedFrame> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, line.to_string());
+
+        let mut parts = line.splitn(3, ' ');
+        let elapsed = parts
+            .next()
+            .and_then(|micros| micros.parse().ok())
+            .map(Duration::from_micros)
+            .ok_or_else(invalid)?;
+        let direction = match parts.next() {
+            Some(">") => FrameDirection::Written,
+            Some("<") => FrameDirection::Read,
+            _ => return Err(invalid()),
+        };
+        let bytes = unescape(parts.next().unwrap_or("")).ok_or_else(invalid)?;
+        Ok(RecordedFrame {
+            elapsed,
+            direction,
+            bytes,
+        })
+    }
+}
+
+/// Reverses [u8::escape_ascii]
+fn unescape(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.bytes();
+    while let Some(b) = chars.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        bytes.push(match chars.next()? {
+            b'r' => b'\r',
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'x' => {
+                let hex = [chars.next()?, chars.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            b => b,
+        });
+    }
+    Some(bytes)
+}
+
+/// Wraps a port and writes every frame passing through it to a sink, one line per frame.
+/// The recording can be served back with a [ReplayPort].
+pub struct RecordingPort<T: SerialPort, W: io::Write> {
+    port: T,
+    sink: W,
+    start: Instant,
+    written: Vec<u8>,
+    read: Vec<u8>,
+}
+
+impl<T: SerialPort, W: io::Write> RecordingPort<T, W> {
+    pub fn new(port: T, sink: W) -> RecordingPort<T, W> {
+        RecordingPort {
+            port,
+            sink,
+            start: Instant::now(),
+            written: Vec::new(),
+            read: Vec::new(),
+        }
+    }
+
+    /// Returns the wrapped port and the sink
+    pub fn into_inner(self) -> (T, W) {
+        (self.port, self.sink)
+    }
+
+    /// Records each complete frame in the buffer and leaves any partial frame
+    fn record_frames(
+        sink: &mut W,
+        elapsed: Duration,
+        direction: FrameDirection,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        while let Some(end) = buf.iter().position(|&b| b == TERMINATION_BYTE) {
+            let frame = RecordedFrame {
+                elapsed,
+                direction,
+                bytes: buf[..end].to_vec(),
+            };
+            buf.drain(..=end);
+            writeln!(sink, "{}", frame.to_line())?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: SerialPort> RecordingPort<T, LineWriter<File>> {
+    /// Records to a newly created file at the given path
+    pub fn create(port: T, path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(RecordingPort::new(
+            port,
+            LineWriter::new(File::create(path)?),
+        ))
+    }
+}
+
+impl<T: SerialPort, W: io::Write> io::Read for RecordingPort<T, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.port.read(buf)?;
+        self.read.extend_from_slice(&buf[..n]);
+        Self::record_frames(
+            &mut self.sink,
+            self.start.elapsed(),
+            FrameDirection::Read,
+            &mut self.read,
+        )?;
+        Ok(n)
+    }
+}
+
+impl<T: SerialPort, W: io::Write> io::Write for RecordingPort<T, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.port.write(buf)?;
+        self.written.extend_from_slice(&buf[..n]);
+        Self::record_frames(
+            &mut self.sink,
+            self.start.elapsed(),
+            FrameDirection::Written,
+            &mut self.written,
+        )?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()?;
+        self.sink.flush()
+    }
+}
+
+impl<T: SerialPort, W: io::Write> SerialPort for RecordingPort<T, W> {}
+
+/// A port that serves back a recording made by a [RecordingPort].
+/// Panics if a command is written that doesn't match the next recorded command.
+/// Reads with nothing left to serve behave like a timeout.
+pub struct ReplayPort {
+    frames: VecDeque<RecordedFrame>,
+    written: Vec<u8>,
+    to_read: VecDeque<u8>,
+}
+
+impl ReplayPort {
+    pub fn new(frames: impl IntoIterator<Item = RecordedFrame>) -> ReplayPort {
+        let mut port = ReplayPort {
+            frames: frames.into_iter().collect(),
+            written: Vec::new(),
+            to_read: VecDeque::new(),
+        };
+        // Serve anything read before the first command
+        port.queue_responses();
+        port
+    }
+
+    /// Reads a recording, one frame per line
+    pub fn from_reader(reader: impl BufRead) -> io::Result<ReplayPort> {
+        let frames = reader
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+            .map(|line| RecordedFrame::from_line(&line?))
+            .collect::<io::Result<Vec<RecordedFrame>>>()?;
+        Ok(ReplayPort::new(frames))
+    }
+
+    /// Reads a recording from the file at the given path
+    pub fn open(path: impl AsRef<Path>) -> io::Result<ReplayPort> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Whether every recorded frame has been replayed
+    pub fn is_finished(&self) -> bool {
+        self.frames.is_empty() && self.to_read.is_empty()
+    }
+
+    fn queue_responses(&mut self) {
+        while let Some(frame) = self.frames.front() {
+            if frame.direction != FrameDirection::Read {
+                break;
+            }
+            let frame = self.frames.pop_front().unwrap();
+            self.to_read.extend(frame.bytes);
+            self.to_read.push_back(TERMINATION_BYTE);
+        }
+    }
+}
+
+impl io::Read for ReplayPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.to_read.len());
+        for (b, read) in buf.iter_mut().zip(self.to_read.drain(..n)) {
+            *b = read;
+        }
+        Ok(n)
+    }
+}
+
+impl io::Write for ReplayPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        while let Some(end) = self.written.iter().position(|&b| b == TERMINATION_BYTE) {
+            let written: Vec<u8> = self.written.drain(..=end).take(end).collect();
+            match self.frames.pop_front() {
+                Some(frame) if frame.direction == FrameDirection::Written => assert_eq!(
+                    written.escape_ascii().to_string(),
+                    frame.bytes.escape_ascii().to_string(),
+                    "written command doesn't match the recording"
+                ),
+                _ => panic!(
+                    "unexpected command {} written after the recording ended",
+                    written.escape_ascii()
+                ),
+            }
+            self.queue_responses();
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for ReplayPort {}
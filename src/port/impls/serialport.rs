@@ -1,5 +1,6 @@
+use crate::port::SynScanPort;
 use crate::util::{SynScanError, SynScanResult};
-use crate::{MotorController, SerialPort};
+use crate::{MotorBoardVersion, MotorController, SerialPort, SingleChannel};
 use std::io;
 use std::time::Duration;
 
@@ -10,7 +11,8 @@ impl SerialPort for SPSerialPort {}
 
 #[cfg(feature = "serialport")]
 impl MotorController<SPSerialPort> {
-    /// Gets a new MotorController using the serialport implentation of a serial port
+    /// Gets a new MotorController using the serialport implentation of a serial port.
+    /// Mounts cabled through a SynScan hand controller work once the hand controller is in PC Direct mode.
     pub fn new_serialport(
         path: impl Into<String>,
         baud_rate: u32,
@@ -23,6 +25,53 @@ impl MotorController<SPSerialPort> {
     }
 }
 
+/// Baud rates used by SkyWatcher motor controllers, in the order they are probed
+pub const PROBE_BAUD_RATES: [u32; 2] = [9600, 115200];
+
+/// A mount found by [detect_mounts]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DetectedMount {
+    pub path: String,
+    pub baud_rate: u32,
+    pub motor_board_version: MotorBoardVersion,
+}
+
+/// Checks whether a SkyWatcher motor controller answers on the given port and baud rate
+#[cfg(feature = "serialport")]
+pub fn probe(
+    path: impl Into<String>,
+    baud_rate: u32,
+    timeout: Duration,
+) -> SynScanResult<MotorBoardVersion> {
+    let port = serialport::new(path.into(), baud_rate)
+        .timeout(timeout)
+        .open()?;
+    let port = SynScanPort::new(port);
+    port.test()?;
+    port.inquire_motor_board_version(SingleChannel::Channel1)
+}
+
+/// Enumerates the serial ports on the system and probes each of them at [PROBE_BAUD_RATES].
+/// Returns every port where a motor controller answered.
+#[cfg(feature = "serialport")]
+pub fn detect_mounts(timeout: Duration) -> SynScanResult<Vec<DetectedMount>> {
+    let mut mounts = Vec::new();
+    for port_info in serialport::available_ports()? {
+        for baud_rate in PROBE_BAUD_RATES {
+            if let Ok(motor_board_version) = probe(port_info.port_name.as_str(), baud_rate, timeout)
+            {
+                mounts.push(DetectedMount {
+                    path: port_info.port_name.clone(),
+                    baud_rate,
+                    motor_board_version,
+                });
+                break;
+            }
+        }
+    }
+    Ok(mounts)
+}
+
 #[cfg(feature = "serialport")]
 impl From<serialport::Error> for SynScanError {
     fn from(e: serialport::Error) -> Self {
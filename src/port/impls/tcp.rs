@@ -0,0 +1,117 @@
+use crate::util::SynScanResult;
+use crate::{MotorController, SerialPort};
+use std::io;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// A serial port exposed as a raw TCP socket, such as by ser2net or a WiFi serial bridge.
+/// The connection is opened lazily and reopened on the next read or write after it fails.
+pub struct TcpSerialPort {
+    addrs: Vec<SocketAddr>,
+    timeout: Duration,
+    stream: Option<TcpStream>,
+}
+
+impl TcpSerialPort {
+    /// Connects to the bridge at the given address.
+    /// Like [TcpStream::connect], each address it resolves to is tried in turn.
+    /// The timeout applies to connecting to each address as well as to each read and write.
+    pub fn connect(addr: impl ToSocketAddrs, timeout: Duration) -> io::Result<TcpSerialPort> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::AddrNotAvailable));
+        }
+        let mut port = TcpSerialPort {
+            addrs,
+            timeout,
+            stream: None,
+        };
+        port.reconnect()?;
+        Ok(port)
+    }
+
+    /// Closes the current connection, if any, and opens a new one to the first address that accepts it.
+    /// Returns the error from the last address if none do.
+    pub fn reconnect(&mut self) -> io::Result<()> {
+        self.stream = None;
+        let mut last_error = None;
+        for addr in &self.addrs {
+            match TcpStream::connect_timeout(addr, self.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    stream.set_write_timeout(Some(self.timeout))?;
+                    stream.set_nodelay(true)?;
+                    self.stream = Some(stream);
+                    return Ok(());
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| io::ErrorKind::AddrNotAvailable.into()))
+    }
+
+    /// The addresses the bridge's address resolved to
+    pub fn addrs(&self) -> &[SocketAddr] {
+        &self.addrs
+    }
+
+    fn stream(&mut self) -> io::Result<&mut TcpStream> {
+        if self.stream.is_none() {
+            self.reconnect()?;
+        }
+        Ok(self.stream.as_mut().unwrap())
+    }
+
+    /// Drops the connection after an error other than a timeout so the next call reconnects
+    fn handle_result<R>(&mut self, result: io::Result<R>) -> io::Result<R> {
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                Err(io::Error::from(io::ErrorKind::TimedOut))
+            }
+            Err(e) if e.kind() != io::ErrorKind::TimedOut => {
+                self.stream = None;
+                Err(e)
+            }
+            result => result,
+        }
+    }
+}
+
+impl io::Read for TcpSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.stream()?.read(buf);
+        if let Ok(0) = result {
+            if !buf.is_empty() {
+                // The other end closed the connection
+                self.stream = None;
+                return Err(io::Error::from(io::ErrorKind::ConnectionAborted));
+            }
+        }
+        self.handle_result(result)
+    }
+}
+
+impl io::Write for TcpSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.stream()?.write(buf);
+        self.handle_result(result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.stream()?.flush();
+        self.handle_result(result)
+    }
+}
+
+impl SerialPort for TcpSerialPort {}
+
+impl MotorController<TcpSerialPort> {
+    /// Gets a new MotorController connected to a serial bridge over TCP
+    pub fn new_tcp(
+        addr: impl ToSocketAddrs,
+        timeout: Duration,
+    ) -> SynScanResult<MotorController<TcpSerialPort>> {
+        let port = TcpSerialPort::connect(addr, timeout)?;
+        Self::new(port)
+    }
+}
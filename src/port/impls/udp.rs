@@ -0,0 +1,117 @@
+use crate::port::commands::TERMINATION_BYTE;
+use crate::util::SynScanResult;
+use crate::{MotorController, SerialPort};
+use std::collections::VecDeque;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+/// The UDP port used by SkyWatcher WiFi adapters and mounts with built in WiFi
+pub const DEFAULT_UDP_PORT: u16 = 11880;
+
+/// Appends [DEFAULT_UDP_PORT] to a host name or IP address that doesn't give a port
+pub fn with_default_port(address: &str) -> String {
+    let has_port = matches!(address.rsplit_once(':'), Some((_, port)) if port.parse::<u16>().is_ok());
+    if has_port {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_UDP_PORT)
+    }
+}
+
+/// A port talking to a mount over UDP, as SkyWatcher WiFi adapters do.
+/// Each command is sent as a single datagram and each reply arrives as one.
+pub struct UdpSerialPort {
+    socket: UdpSocket,
+    to_write: Vec<u8>,
+    to_read: VecDeque<u8>,
+}
+
+impl UdpSerialPort {
+    /// Binds a local socket and connects it to the mount at the given address.
+    /// The timeout applies to each read and write.
+    pub fn connect(addr: impl ToSocketAddrs, timeout: Duration) -> io::Result<UdpSerialPort> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::AddrNotAvailable))?;
+        let local_addr = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local_addr)?;
+        socket.connect(addr)?;
+        socket.set_read_timeout(Some(timeout))?;
+        socket.set_write_timeout(Some(timeout))?;
+        Ok(UdpSerialPort {
+            socket,
+            to_write: Vec::new(),
+            to_read: VecDeque::new(),
+        })
+    }
+
+    /// Throws away replies to earlier commands that arrived after they timed out or failed
+    fn discard_late_replies(&mut self) -> io::Result<()> {
+        self.to_read.clear();
+        self.socket.set_nonblocking(true)?;
+        let mut datagram = [0; 64];
+        while self.socket.recv(&mut datagram).is_ok() {}
+        self.socket.set_nonblocking(false)
+    }
+}
+
+impl io::Read for UdpSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.to_read.is_empty() && !buf.is_empty() {
+            let mut datagram = [0; 64];
+            let n = match self.socket.recv(&mut datagram) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    return Err(io::Error::from(io::ErrorKind::TimedOut))
+                }
+                result => result?,
+            };
+            self.to_read.extend(&datagram[..n]);
+        }
+
+        let n = buf.len().min(self.to_read.len());
+        for (b, read) in buf.iter_mut().zip(self.to_read.drain(..n)) {
+            *b = read;
+        }
+        Ok(n)
+    }
+}
+
+impl io::Write for UdpSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.to_write.extend_from_slice(buf);
+        if self.to_write.contains(&TERMINATION_BYTE) {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Sends everything written so far as a single datagram
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.to_write.is_empty() {
+            self.discard_late_replies()?;
+            self.socket.send(&self.to_write)?;
+            self.to_write.clear();
+        }
+        Ok(())
+    }
+}
+
+impl SerialPort for UdpSerialPort {}
+
+impl MotorController<UdpSerialPort> {
+    /// Gets a new MotorController talking to a WiFi mount over UDP.
+    /// Mounts usually listen on [DEFAULT_UDP_PORT].
+    pub fn new_udp(
+        addr: impl ToSocketAddrs,
+        timeout: Duration,
+    ) -> SynScanResult<MotorController<UdpSerialPort>> {
+        let port = UdpSerialPort::connect(addr, timeout)?;
+        Self::new(port)
+    }
+}
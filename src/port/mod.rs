@@ -1,4 +1,5 @@
 pub mod commands;
+mod retry;
 mod serial_port;
 mod synscan_port;
 
@@ -11,8 +12,11 @@ mod impls {
     pub mod mock;
     #[cfg(feature = "serialport")]
     pub mod serialport;
+    pub mod record;
+    pub mod tcp;
+    pub mod udp;
 }
 
-pub use channels::*;
+pub use retry::*;
 pub use serial_port::*;
 pub use impls::*;
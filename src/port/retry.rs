@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+/// Describes how commands are retried after a communication error.
+/// Only commands that are safe to send more than once are retried.
+/// Errors reported by the controller itself are never retried.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of times a command is resent after the first attempt fails
+    pub max_retries: u32,
+    /// How long to wait before resending a command
+    pub retry_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, retry_delay: Duration) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            retry_delay,
+        }
+    }
+
+    /// A policy that never retries
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new(0, Duration::ZERO)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::none()
+    }
+}
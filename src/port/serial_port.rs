@@ -1,7 +1,16 @@
+use crate::RetryPolicy;
 use std::io;
 use std::sync::Mutex;
 
 /// Defines a serial port that the library can use
 pub trait SerialPort: io::Read + io::Write {}
 
-pub(crate) struct SynScanPort<T: SerialPort>(pub(crate) Mutex<T>);
+/// Any serial port, for when the kind of port is only known at runtime
+pub type BoxedSerialPort = Box<dyn SerialPort + Send>;
+
+impl SerialPort for BoxedSerialPort {}
+
+pub(crate) struct SynScanPort<T: SerialPort> {
+    pub(crate) port: Mutex<T>,
+    pub(crate) retry_policy: Mutex<RetryPolicy>,
+}
@@ -2,11 +2,15 @@ use crate::port::commands::*;
 use crate::port::SynScanPort;
 use crate::util::*;
 use crate::*;
-use std::{io, slice};
 use std::sync::Mutex;
+use std::{io, slice, thread};
 
 /// Converts bytes returned from the mount into the number it describes
 pub(crate) fn bytes_to_number(data: Vec<u8>) -> SynScanResult<u32> {
+    // Numbers are sent as up to three whole bytes, so a reply that lost a digit is rejected
+    if !data.len().is_multiple_of(2) || data.len() > 6 {
+        return Err(SynScanError::InvalidResponse);
+    }
     let mut i = data.len() + 1;
     let mut n_vec = vec![0; data.len()];
     for b in data {
@@ -18,13 +22,7 @@ pub(crate) fn bytes_to_number(data: Vec<u8>) -> SynScanResult<u32> {
     }
 
     let hex_string: String = n_vec.into_iter().map(|b| b as char).collect();
-    match u32::from_str_radix(&hex_string, 16) {
-        Ok(v) => Ok(v),
-        Err(e) => Err(SynScanError::CommunicationError(io::Error::new(
-            io::ErrorKind::InvalidData,
-            e,
-        ))),
-    }
+    u32::from_str_radix(&hex_string, 16).map_err(|_| SynScanError::InvalidResponse)
 }
 
 /// Converts a number into a bytes the mount can understand.
@@ -53,14 +51,19 @@ impl<T: SerialPort> SynScanPort<T> {
     where
         T: SerialPort,
     {
-        SynScanPort(Mutex::new(port))
+        SynScanPort {
+            port: Mutex::new(port),
+            retry_policy: Mutex::new(RetryPolicy::default()),
+        }
     }
 
-    fn read_byte(port: &mut impl SerialPort) -> io::Result<u8> {
+    /// Reads a single byte, also appending it to the raw bytes of the response
+    fn read_byte(port: &mut impl SerialPort, raw: &mut Vec<u8>) -> io::Result<u8> {
         let mut b: u8 = 0;
         if 0 == port.read(slice::from_mut(&mut b))? {
             return Err(io::Error::from(io::ErrorKind::TimedOut));
         }
+        raw.push(b);
         Ok(b)
     }
 
@@ -79,20 +82,31 @@ impl<T: SerialPort> SynScanPort<T> {
             '5' => SynScanError::DriverSleeping,
             '7' => SynScanError::PECTrainingRunning,
             '8' => SynScanError::NoValidPECData,
-            _ => SynScanError::CommunicationError(io::Error::from(io::ErrorKind::InvalidData)),
+            _ => SynScanError::UnknownErrorCode(code),
         }
     }
 
-    fn consume_term_char(port: &mut impl SerialPort) -> io::Result<()> {
-        let termination_char = Self::read_byte(port)?;
+    fn consume_term_char(port: &mut impl SerialPort, raw: &mut Vec<u8>) -> SynScanResult<()> {
+        let termination_char = Self::read_byte(port, raw)?;
         if termination_char != TERMINATION_BYTE {
-            return Err(io::Error::from(io::ErrorKind::InvalidData));
+            Self::discard_to_term_char(port, raw);
+            return Err(SynScanError::InvalidResponse);
         }
         Ok(())
     }
 
-    fn read_response(port: &mut impl SerialPort) -> SynScanResult<Vec<u8>> {
-        let first_byte = Self::read_byte(port)?;
+    /// Throws away input up to and including the next termination byte.
+    /// This resynchronizes the stream after a malformed response so the next reply isn't misread.
+    fn discard_to_term_char(port: &mut impl SerialPort, raw: &mut Vec<u8>) {
+        while let Ok(byte) = Self::read_byte(port, raw) {
+            if byte == TERMINATION_BYTE {
+                break;
+            }
+        }
+    }
+
+    fn read_response(port: &mut impl SerialPort, raw: &mut Vec<u8>) -> SynScanResult<Vec<u8>> {
+        let first_byte = Self::read_byte(port, raw)?;
 
         if first_byte == SUCCESS_BYTE {
             // Successful
@@ -100,64 +114,167 @@ impl<T: SerialPort> SynScanPort<T> {
             let mut buf = Vec::with_capacity(MAX_VALID_RESPONSE);
             let mut i = 0;
             loop {
-                let byte = Self::read_byte(port)?;
+                let byte = Self::read_byte(port, raw)?;
                 if byte == TERMINATION_BYTE {
                     break;
                 } else if i < MAX_VALID_RESPONSE {
                     buf.push(byte);
                     i += 1;
                 } else {
-                    return Err(SynScanError::CommunicationError(io::Error::from(
-                        io::ErrorKind::InvalidData,
-                    )));
+                    Self::discard_to_term_char(port, raw);
+                    return Err(SynScanError::InvalidResponse);
                 }
             }
             Ok(buf)
         } else if first_byte == ERROR_BYTE {
             // Error Code
-            let error_code = Self::read_byte(port)?;
-            Self::consume_term_char(port)?;
+            let error_code = Self::read_byte(port, raw)?;
+            Self::consume_term_char(port, raw)?;
             Err(Self::resolve_controller_error(error_code))
         } else {
-            Err(SynScanError::CommunicationError(io::Error::from(
-                io::ErrorKind::InvalidData,
-            )))
+            if first_byte != TERMINATION_BYTE {
+                Self::discard_to_term_char(port, raw);
+            }
+            Err(SynScanError::InvalidResponse)
         }
     }
 
-    fn raw_send_cmd(
+    /// Writes a command, then reads and parses the response.
+    /// On failure the error is returned with the raw bytes that were received.
+    fn send_frame<R>(
+        port: &mut impl SerialPort,
+        full_cmd: &[u8],
+        parse: &mut impl FnMut(Vec<u8>) -> SynScanResult<R>,
+    ) -> Result<R, (SynScanError, Vec<u8>)> {
+        #[cfg(feature = "log")]
+        let start = std::time::Instant::now();
+        #[cfg(feature = "log")]
+        log::trace!("-> {}", full_cmd.escape_ascii());
+
+        let mut raw = Vec::new();
+        let result = match port.write_all(full_cmd) {
+            Ok(_) => Self::read_response(port, &mut raw).and_then(&mut *parse),
+            Err(e) => Err(SynScanError::CommunicationError(e)),
+        };
+
+        #[cfg(feature = "log")]
+        match &result {
+            Ok(_) => log::trace!("<- {} ({:?})", raw.escape_ascii(), start.elapsed()),
+            Err(e) => log::debug!(
+                "{} failed: {:?} after receiving {} ({:?})",
+                full_cmd.escape_ascii(),
+                e,
+                raw.escape_ascii(),
+                start.elapsed()
+            ),
+        }
+
+        result.map_err(|e| (e, raw))
+    }
+
+    pub(crate) fn raw_send_cmd(
         &self,
         cmd: u8,
         channel: impl Channel,
         bytes: &[u8],
     ) -> SynScanResult<Vec<u8>> {
+        self.send_cmd_parsed(cmd, channel, bytes, Ok)
+    }
+
+    /// Sends a command and parses the response, retrying according to the retry policy.
+    /// Responses that can't be parsed are retried like any other invalid response.
+    fn send_cmd_parsed<R>(
+        &self,
+        cmd: u8,
+        channel: impl Channel,
+        bytes: &[u8],
+        mut parse: impl FnMut(Vec<u8>) -> SynScanResult<R>,
+    ) -> SynScanResult<R> {
         let mut full_cmd = vec![QUERY_BYTE, cmd, channel.get_byte()];
         full_cmd.extend(bytes);
         full_cmd.push(TERMINATION_BYTE);
 
-        let mut port_lock = self.0.lock().unwrap();
+        let retry_policy = self.get_retry_policy();
+        let mut port_lock = self.port.lock().unwrap();
 
-        match port_lock.write_all(full_cmd.as_slice()) {
-            Ok(_) => Self::read_response(&mut *port_lock),
-            Err(e) => Err(SynScanError::CommunicationError(e)),
+        let mut retries = 0;
+        loop {
+            match Self::send_frame(&mut *port_lock, full_cmd.as_slice(), &mut parse) {
+                Ok(response) => return Ok(response),
+                Err((e, response)) => {
+                    // After a timeout the reply may still arrive, so it is thrown away so the next
+                    // command doesn't read it as its own.
+                    // Malformed replies have already been discarded up to their termination byte.
+                    if let SynScanError::CommunicationError(io_error) = &e {
+                        if matches!(
+                            io_error.kind(),
+                            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                        ) {
+                            Self::discard_to_term_char(&mut *port_lock, &mut Vec::new());
+                        }
+                    }
+                    if e.is_communication_error()
+                        && retries < retry_policy.max_retries
+                        && is_retry_safe(cmd)
+                    {
+                        retries += 1;
+                        thread::sleep(retry_policy.retry_delay);
+                        continue;
+                    }
+                    return Err(SynScanError::CommandFailed {
+                        command: cmd,
+                        channel: channel.get_byte(),
+                        payload: bytes.to_vec(),
+                        response,
+                        source: Box::new(e),
+                    });
+                }
+            }
         }
     }
 
-    /// Inquires the mount for bytes
-    pub fn inquire_bytes(&self, cmd: u8, channel: impl Channel) -> SynScanResult<Vec<u8>> {
-        let response = self.raw_send_cmd(cmd, channel, &[])?;
-        if response.is_empty() {
-            Err(SynScanError::CommunicationError(io::Error::from(
-                io::ErrorKind::InvalidData,
-            )))
-        } else {
-            Ok(response)
-        }
+    /// Writes a command without waiting for a reply, for commands the controller may not answer
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn send_cmd_without_reply(
+        &self,
+        cmd: u8,
+        channel: impl Channel,
+    ) -> SynScanResult<()> {
+        let full_cmd = [QUERY_BYTE, cmd, channel.get_byte(), TERMINATION_BYTE];
+        let mut port = self.port.lock().unwrap();
+        port.write_all(&full_cmd)?;
+        port.flush()?;
+        Ok(())
+    }
+
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        *self.retry_policy.lock().unwrap()
+    }
+
+    pub fn set_retry_policy(&self, retry_policy: RetryPolicy) {
+        *self.retry_policy.lock().unwrap() = retry_policy;
+    }
+
+    /// Inquires the mount and parses the non-empty response.
+    /// Parsing errors are retried and returned with the command and response that caused them.
+    pub fn inquire_parsed<R>(
+        &self,
+        cmd: u8,
+        channel: impl Channel,
+        parse: impl Fn(&[u8]) -> SynScanResult<R>,
+    ) -> SynScanResult<R> {
+        self.send_cmd_parsed(cmd, channel, &[], |response| {
+            if response.is_empty() {
+                Err(SynScanError::InvalidResponse)
+            } else {
+                parse(&response)
+            }
+        })
     }
 
     /// Inquires the mount for a number
     pub fn inquire_number(&self, cmd: u8, channel: impl Channel) -> SynScanResult<u32> {
-        bytes_to_number(self.inquire_bytes(cmd, channel)?)
+        self.inquire_parsed(cmd, channel, |bytes| bytes_to_number(bytes.to_vec()))
     }
 
     /// Sends a responseless command to the mount with bytes as the payload
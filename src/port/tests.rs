@@ -1,6 +1,17 @@
 use super::synscan_port::{bytes_to_number, number_to_bytes};
+use crate::port::commands::*;
 use crate::port::mock::MockSynScanPort;
+use crate::port::SynScanPort;
+use crate::record::*;
+use crate::tcp::TcpSerialPort;
+use crate::udp::UdpSerialPort;
+use crate::util::SynScanError;
 use crate::*;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+use std::thread;
+use std::time::Duration;
 
 impl MockSynScanPort {
     pub(crate) fn add_valid_number(&self, number: u32, num_bytes: usize) {
@@ -37,6 +48,7 @@ fn test_get_number_from_response() {
         bytes_to_number(vec![b'5', b'6', b'3', b'4', b'1', b'2']).unwrap(),
         0x123456
     );
+    assert!(bytes_to_number(vec![b'1', b'9', b'0', b'0', b'8']).is_err());
 }
 
 #[test]
@@ -52,3 +64,450 @@ fn test_get_data_from_number() {
         vec![b'4', b'0', b'E', b'2', b'0', b'1']
     );
 }
+
+#[test]
+fn test_resync_after_invalid_response() {
+    let mock = MockSynScanPort::new();
+    let port = SynScanPort::new(mock.clone());
+
+    // A stray byte in front of the reply
+    mock.add_response(b"x=1A\r");
+    mock.add_valid_number(0x2B, 2);
+    assert!(port
+        .inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+        .is_err());
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        0x2B
+    );
+
+    // A reply that is too long
+    mock.add_response(b"=12345678\r");
+    mock.add_valid_number(0x3C, 2);
+    assert!(port
+        .inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+        .is_err());
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        0x3C
+    );
+}
+
+#[test]
+fn test_reply_with_dropped_digit() {
+    let mock = MockSynScanPort::new();
+    let port = SynScanPort::new(mock.clone());
+
+    mock.add_response(b"=19008\r");
+    mock.add_valid_number(0x2B, 2);
+    let error = port
+        .inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+        .unwrap_err();
+    assert!(matches!(error.root_cause(), SynScanError::InvalidResponse));
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        0x2B
+    );
+}
+
+#[test]
+fn test_retry_reply_with_dropped_digit() {
+    let mock = MockSynScanPort::new();
+    let port = SynScanPort::new(mock.clone());
+    port.set_retry_policy(RetryPolicy::new(1, Duration::ZERO));
+
+    mock.add_response(b"=19008\r");
+    mock.add_valid_number(0x2B, 2);
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        0x2B
+    );
+    mock.check_written(b":j1\r:j1\r");
+}
+
+#[test]
+fn test_retry_safe_command() {
+    let mock = MockSynScanPort::new();
+    let port = SynScanPort::new(mock.clone());
+    port.set_retry_policy(RetryPolicy::new(2, Duration::ZERO));
+
+    mock.add_response(b"x\r");
+    mock.add_response(b"x\r");
+    mock.add_valid_number(0x2B, 2);
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel2)
+            .unwrap(),
+        0x2B
+    );
+    mock.check_written(b":j2\r:j2\r:j2\r");
+
+    mock.add_response(b"x\r");
+    mock.add_response(b"x\r");
+    mock.add_response(b"x\r");
+    assert!(port
+        .inquire_number(INQUIRE_POSITION, SingleChannel::Channel2)
+        .is_err());
+    mock.check_written(b":j2\r:j2\r:j2\r");
+}
+
+/// A port whose replies can arrive too late, after the read timed out
+struct LateReplyPort {
+    /// Each read either gets the next chunk of bytes or times out if it is None
+    chunks: VecDeque<Option<Vec<u8>>>,
+    to_read: VecDeque<u8>,
+}
+
+impl Read for LateReplyPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.to_read.is_empty() {
+            match self.chunks.pop_front().flatten() {
+                Some(chunk) => self.to_read.extend(chunk),
+                None => return Err(std::io::ErrorKind::TimedOut.into()),
+            }
+        }
+        let n = buf.len().min(self.to_read.len());
+        for (b, read) in buf.iter_mut().zip(self.to_read.drain(..n)) {
+            *b = read;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for LateReplyPort {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for LateReplyPort {}
+
+#[test]
+fn test_retry_discards_late_reply() {
+    let port = SynScanPort::new(LateReplyPort {
+        chunks: VecDeque::from([
+            None,
+            Some(b"=010000\r".to_vec()),
+            Some(b"=020000\r".to_vec()),
+            Some(b"=101\r".to_vec()),
+        ]),
+        to_read: VecDeque::new(),
+    });
+    port.set_retry_policy(RetryPolicy::new(1, Duration::ZERO));
+
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        2
+    );
+    // The following command gets its own reply
+    assert_eq!(
+        port.raw_send_cmd(INQUIRE_STATUS, SingleChannel::Channel1, &[])
+            .unwrap(),
+        b"101"
+    );
+}
+
+#[test]
+fn test_discard_late_reply_without_retries() {
+    let port = SynScanPort::new(LateReplyPort {
+        chunks: VecDeque::from([None, Some(b"=010000\r".to_vec()), Some(b"=101\r".to_vec())]),
+        to_read: VecDeque::new(),
+    });
+
+    let error = port
+        .inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+        .unwrap_err();
+    assert!(matches!(
+        error.root_cause(),
+        SynScanError::CommunicationError(_)
+    ));
+    assert_eq!(
+        std::error::Error::source(error.root_cause())
+            .unwrap()
+            .to_string(),
+        std::io::Error::from(std::io::ErrorKind::TimedOut).to_string()
+    );
+    assert_eq!(
+        port.raw_send_cmd(INQUIRE_STATUS, SingleChannel::Channel1, &[])
+            .unwrap(),
+        b"101"
+    );
+}
+
+#[test]
+fn test_no_retry_unsafe_command() {
+    let mock = MockSynScanPort::new();
+    let port = SynScanPort::new(mock.clone());
+    port.set_retry_policy(RetryPolicy::new(2, Duration::ZERO));
+
+    mock.add_response(b"x\r");
+    assert!(port
+        .send_cmd_number(SET_GOTO_TARGET_INCREMENT, SingleChannel::Channel1, 10, 6)
+        .is_err());
+    mock.check_correct_number_written(SET_GOTO_TARGET_INCREMENT, SingleChannel::Channel1, 10, 6);
+
+    // Errors from the controller aren't retried
+    mock.add_error_response(b'2');
+    assert!(port.send_cmd(STOP_MOTION, SingleChannel::Channel1).is_err());
+    mock.check_correct(STOP_MOTION, SingleChannel::Channel1);
+}
+
+/// Answers a command frame the way a minimal mount would
+fn simulated_response(frame: &[u8]) -> Vec<u8> {
+    let mut response = vec![SUCCESS_BYTE];
+    response.extend(match frame[1] {
+        INQUIRE_COUNTS_PER_REVOLUTION => number_to_bytes(360, 6),
+        INQUIRE_TIMER_INTERRUPT_FREQUENCY => number_to_bytes(1000, 6),
+        INQUIRE_HIGH_SPEED_RATIO => number_to_bytes(16, 2),
+        INQUIRE_POSITION => number_to_bytes(0x800000 + 25, 6),
+        _ => vec![],
+    });
+    response.push(TERMINATION_BYTE);
+    response
+}
+
+/// Serves a minimal mount over TCP, closing each connection after the given number of commands
+fn spawn_tcp_mount(commands_per_connection: usize) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut frame = Vec::new();
+            let mut handled = 0;
+            let mut byte = [0];
+            while handled < commands_per_connection && stream.read(&mut byte).unwrap_or(0) == 1 {
+                if byte[0] != TERMINATION_BYTE {
+                    frame.push(byte[0]);
+                    continue;
+                }
+                stream.write_all(&simulated_response(&frame)).unwrap();
+                frame.clear();
+                handled += 1;
+            }
+        }
+    });
+    addr
+}
+
+#[test]
+fn test_tcp_port() {
+    let addr = spawn_tcp_mount(usize::MAX);
+    let mc = MotorController::new_tcp(addr, Duration::from_secs(1)).unwrap();
+    assert_eq!(mc.get_motor_parameters().timer_interrupt_freq, 1000);
+    assert_eq!(mc.inquire_pos(SingleChannel::Channel1).unwrap(), 25);
+}
+
+#[test]
+fn test_tcp_port_tries_each_address() {
+    // Nothing listens on a port whose listener has been dropped
+    let refused = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let addr = spawn_tcp_mount(usize::MAX);
+    let tcp_port = TcpSerialPort::connect(&[refused, addr][..], Duration::from_secs(1)).unwrap();
+    assert_eq!(tcp_port.addrs(), [refused, addr]);
+
+    let port = SynScanPort::new(tcp_port);
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        0x800000 + 25
+    );
+}
+
+#[test]
+fn test_tcp_port_reconnects() {
+    let addr = spawn_tcp_mount(1);
+    let port = SynScanPort::new(TcpSerialPort::connect(addr, Duration::from_secs(1)).unwrap());
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        0x800000 + 25
+    );
+    // The server has dropped the first connection
+    assert!(port
+        .inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+        .is_err());
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        0x800000 + 25
+    );
+}
+
+#[test]
+fn test_recorded_frame_lines() {
+    let frame = RecordedFrame {
+        elapsed: Duration::from_micros(1042),
+        direction: FrameDirection::Written,
+        bytes: b":j1".to_vec(),
+    };
+    assert_eq!(frame.to_line(), "1042 > :j1");
+    assert_eq!(RecordedFrame::from_line("1042 > :j1").unwrap(), frame);
+
+    let frame = RecordedFrame {
+        elapsed: Duration::ZERO,
+        direction: FrameDirection::Read,
+        bytes: vec![b'=', 0x00, b'\\', b' '],
+    };
+    assert_eq!(RecordedFrame::from_line(&frame.to_line()).unwrap(), frame);
+
+    assert!(RecordedFrame::from_line("12 ? :j1").is_err());
+    assert!(RecordedFrame::from_line("abc > :j1").is_err());
+}
+
+#[test]
+fn test_record_and_replay() {
+    let path = std::env::temp_dir().join(format!("synscan-record-{}.txt", std::process::id()));
+
+    let mock = MockSynScanPort::new();
+    let port = SynScanPort::new(RecordingPort::create(mock.clone(), &path).unwrap());
+    mock.add_valid_number(0x800000 + 25, 6);
+    mock.add_error_response(b'2');
+    port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+        .unwrap();
+    assert!(port.send_cmd(START_MOTION, MultiChannel::Both).is_err());
+    drop(port);
+
+    let replay = ReplayPort::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let port = SynScanPort::new(replay);
+    assert_eq!(
+        port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+            .unwrap(),
+        0x800000 + 25
+    );
+    assert!(matches!(
+        port.send_cmd(START_MOTION, MultiChannel::Both)
+            .unwrap_err()
+            .root_cause(),
+        SynScanError::MotorNotStopped
+    ));
+    assert!(port.port.lock().unwrap().is_finished());
+}
+
+#[test]
+#[should_panic(expected = "doesn't match the recording")]
+fn test_replay_mismatch() {
+    let replay = ReplayPort::from_reader("0 > :j1\n0 < =000080\n".as_bytes()).unwrap();
+    let port = SynScanPort::new(replay);
+    let _ = port.inquire_number(INQUIRE_POSITION, SingleChannel::Channel2);
+}
+
+#[test]
+fn test_error_context() {
+    let mock = MockSynScanPort::new();
+    let port = SynScanPort::new(mock.clone());
+
+    mock.add_error_response(b'2');
+    let e = port
+        .send_cmd_number(SET_STEP_PERIOD, SingleChannel::Channel1, 0x12, 2)
+        .unwrap_err();
+    assert!(matches!(e.root_cause(), SynScanError::MotorNotStopped));
+    match &e {
+        SynScanError::CommandFailed {
+            command,
+            channel,
+            payload,
+            response,
+            ..
+        } => {
+            assert_eq!(*command, SET_STEP_PERIOD);
+            assert_eq!(*channel, b'1');
+            assert_eq!(payload, b"12");
+            assert_eq!(response, b"!2\r");
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+    assert_eq!(
+        e.to_string(),
+        "command \":I112\" failed, received \"!2\\r\""
+    );
+    assert_eq!(
+        std::error::Error::source(&e).unwrap().to_string(),
+        "Motor Must be Stopped"
+    );
+    assert!(!e.is_communication_error());
+
+    mock.add_error_response(b'9');
+    let e = port
+        .send_cmd(START_MOTION, SingleChannel::Channel2)
+        .unwrap_err();
+    assert!(matches!(
+        e.root_cause(),
+        SynScanError::UnknownErrorCode(b'9')
+    ));
+
+    mock.add_valid_response(b"XY");
+    let e = port
+        .inquire_number(INQUIRE_POSITION, SingleChannel::Channel2)
+        .unwrap_err();
+    assert!(e.is_communication_error());
+    match e {
+        SynScanError::CommandFailed { response, .. } => assert_eq!(response, b"=XY\r"),
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+/// Serves a minimal mount over UDP. The first datagram gets a late reply after the given delay.
+fn spawn_udp_mount(first_reply_delay: Duration) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut datagram = [0; 64];
+        let mut delay = first_reply_delay;
+        while let Ok((n, peer)) = socket.recv_from(&mut datagram) {
+            thread::sleep(delay);
+            delay = Duration::ZERO;
+            let frame = &datagram[..n - 1];
+            socket.send_to(&simulated_response(frame), peer).unwrap();
+        }
+    });
+    addr
+}
+
+#[test]
+fn test_udp_default_port() {
+    assert_eq!(udp::with_default_port("192.168.4.1"), "192.168.4.1:11880");
+    assert_eq!(
+        udp::with_default_port("mount.local:2000"),
+        "mount.local:2000"
+    );
+}
+
+#[test]
+fn test_udp_port() {
+    let addr = spawn_udp_mount(Duration::ZERO);
+    let mc = MotorController::new_udp(addr, Duration::from_secs(1)).unwrap();
+    assert_eq!(
+        mc.get_motor_parameters().high_speed_ratio[SingleChannel::Channel2],
+        16
+    );
+    assert_eq!(mc.inquire_pos(SingleChannel::Channel2).unwrap(), 25);
+}
+
+#[test]
+fn test_udp_port_discards_late_reply() {
+    let addr = spawn_udp_mount(Duration::from_millis(200));
+    let port = SynScanPort::new(UdpSerialPort::connect(addr, Duration::from_millis(50)).unwrap());
+    assert!(port
+        .inquire_number(INQUIRE_POSITION, SingleChannel::Channel1)
+        .is_err());
+    thread::sleep(Duration::from_millis(300));
+    // The late position reply must not be taken as the reply to this command
+    assert_eq!(
+        port.inquire_number(INQUIRE_TIMER_INTERRUPT_FREQUENCY, SingleChannel::Channel1)
+            .unwrap(),
+        1000
+    );
+}
@@ -2,6 +2,7 @@
 use crate::port::channels::*;
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BiChannelValue<T> {
     channel1: T,
     channel2: T,
@@ -12,7 +13,6 @@ impl<T> BiChannelValue<T> {
         BiChannelValue { channel1, channel2 }
     }
 
-    #[allow(dead_code)]
     pub fn new_from_fn<F>(mut f: F) -> BiChannelValue<T>
     where
         F: FnMut(SingleChannel) -> T,
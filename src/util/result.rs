@@ -1,9 +1,12 @@
 use std::error::Error;
+#[cfg(feature = "config")]
+use std::path::PathBuf;
 use std::{fmt, io};
 
 pub type SynScanResult<T> = Result<T, SynScanError>;
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum SynScanError {
     UnknownCommand,
     CommandLengthError,
@@ -13,7 +16,50 @@ pub enum SynScanError {
     DriverSleeping,
     PECTrainingRunning,
     NoValidPECData,
+    /// The controller replied with an error code this library doesn't know
+    UnknownErrorCode(u8),
+    /// The reply from the controller was malformed
+    InvalidResponse,
     CommunicationError(io::Error),
+    /// Wraps an error with the command that caused it and the raw bytes received in reply
+    CommandFailed {
+        command: u8,
+        channel: u8,
+        payload: Vec<u8>,
+        response: Vec<u8>,
+        source: Box<SynScanError>,
+    },
+    /// A configuration has an invalid value
+    #[cfg(feature = "config")]
+    InvalidConfig {
+        /// The path to the offending field, e.g. `transport.serial.baud_rate`
+        field: String,
+        reason: String,
+    },
+    /// A configuration file couldn't be read
+    #[cfg(feature = "config")]
+    ConfigFileError {
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+impl SynScanError {
+    /// Returns the underlying error, without any command context
+    pub fn root_cause(&self) -> &SynScanError {
+        match self {
+            SynScanError::CommandFailed { source, .. } => source.root_cause(),
+            e => e,
+        }
+    }
+
+    /// Whether the error came from the connection rather than from the controller
+    pub fn is_communication_error(&self) -> bool {
+        matches!(
+            self.root_cause(),
+            SynScanError::CommunicationError(_) | SynScanError::InvalidResponse
+        )
+    }
 }
 
 impl fmt::Display for SynScanError {
@@ -27,13 +73,51 @@ impl fmt::Display for SynScanError {
             SynScanError::DriverSleeping => "Driver is Sleeping",
             SynScanError::PECTrainingRunning => "PEC Training is Running",
             SynScanError::NoValidPECData => "No Valid PEC Data",
-            SynScanError::CommunicationError(e) => return write!(f, "Communication Error: {}", e),
+            SynScanError::UnknownErrorCode(code) => {
+                return write!(f, "Unknown Error Code: {}", code.escape_ascii())
+            }
+            SynScanError::InvalidResponse => "Invalid Response",
+            SynScanError::CommunicationError(_) => "Communication Error",
+            SynScanError::CommandFailed {
+                command,
+                channel,
+                payload,
+                response,
+                ..
+            } => {
+                return write!(
+                    f,
+                    "command \":{}{}{}\" failed, received \"{}\"",
+                    command.escape_ascii(),
+                    channel.escape_ascii(),
+                    payload.escape_ascii(),
+                    response.escape_ascii()
+                )
+            }
+            #[cfg(feature = "config")]
+            SynScanError::InvalidConfig { field, reason } => {
+                return write!(f, "Invalid Configuration: {}: {}", field, reason)
+            }
+            #[cfg(feature = "config")]
+            SynScanError::ConfigFileError { path, .. } => {
+                return write!(f, "Couldn't Read Configuration File {}", path.display())
+            }
         };
         write!(f, "{}", description)
     }
 }
 
-impl Error for SynScanError {}
+impl Error for SynScanError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SynScanError::CommunicationError(e) => Some(e),
+            SynScanError::CommandFailed { source, .. } => Some(source.as_ref()),
+            #[cfg(feature = "config")]
+            SynScanError::ConfigFileError { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 impl From<io::Error> for SynScanError {
     fn from(e: io::Error) -> Self {